@@ -0,0 +1,105 @@
+//! Character classes used when lexing identifiers, mirroring Java's
+//! `Character.isJavaIdentifierStart` and `Character.isJavaIdentifierPart`.
+//!
+//! std doesn't expose unicode general categories, so the categories Java
+//! checks are approximated: letters with `char::is_alphabetic`, digits with
+//! `char::is_numeric`, and the remaining categories (currency symbols,
+//! connector punctuation, combining marks and format chars) with the tables
+//! below.
+
+/// Currency symbols (Sc)
+const CURRENCY_SYMBOLS: &[(char, char)] = &[
+    ('\u{a2}', '\u{a5}'), ('\u{58f}', '\u{58f}'), ('\u{60b}', '\u{60b}'),
+    ('\u{7fe}', '\u{7ff}'), ('\u{9f2}', '\u{9f3}'), ('\u{9fb}', '\u{9fb}'),
+    ('\u{af1}', '\u{af1}'), ('\u{bf9}', '\u{bf9}'), ('\u{e3f}', '\u{e3f}'),
+    ('\u{17db}', '\u{17db}'), ('\u{20a0}', '\u{20c0}'), ('\u{a838}', '\u{a838}'),
+    ('\u{fdfc}', '\u{fdfc}'), ('\u{fe69}', '\u{fe69}'), ('\u{ff04}', '\u{ff04}'),
+    ('\u{ffe0}', '\u{ffe1}'), ('\u{ffe5}', '\u{ffe6}'), ('\u{11fdd}', '\u{11fe0}'),
+    ('\u{1e2ff}', '\u{1e2ff}'), ('\u{1ecb0}', '\u{1ecb0}'),
+];
+
+/// Connector punctuation (Pc), other than '_'
+const CONNECTOR_PUNCTUATION: &[(char, char)] = &[
+    ('\u{203f}', '\u{2040}'), ('\u{2054}', '\u{2054}'), ('\u{fe33}', '\u{fe34}'),
+    ('\u{fe4d}', '\u{fe4f}'), ('\u{ff3f}', '\u{ff3f}'),
+];
+
+/// Combining marks (Mn, Mc) which `char::is_alphanumeric` doesn't cover
+const COMBINING_MARKS: &[(char, char)] = &[
+    ('\u{300}', '\u{36f}'), ('\u{483}', '\u{487}'), ('\u{591}', '\u{5bd}'),
+    ('\u{5bf}', '\u{5bf}'), ('\u{5c1}', '\u{5c2}'), ('\u{5c4}', '\u{5c5}'),
+    ('\u{5c7}', '\u{5c7}'), ('\u{610}', '\u{61a}'), ('\u{64b}', '\u{65f}'),
+    ('\u{670}', '\u{670}'), ('\u{6d6}', '\u{6dc}'), ('\u{6df}', '\u{6e4}'),
+    ('\u{6e7}', '\u{6e8}'), ('\u{6ea}', '\u{6ed}'), ('\u{711}', '\u{711}'),
+    ('\u{730}', '\u{74a}'), ('\u{7eb}', '\u{7f3}'), ('\u{e31}', '\u{e31}'),
+    ('\u{e34}', '\u{e3a}'), ('\u{e47}', '\u{e4e}'), ('\u{1ab0}', '\u{1abd}'),
+    ('\u{1dc0}', '\u{1dff}'), ('\u{20d0}', '\u{20dc}'), ('\u{20e1}', '\u{20e1}'),
+    ('\u{20e5}', '\u{20f0}'), ('\u{302a}', '\u{302f}'), ('\u{3099}', '\u{309a}'),
+    ('\u{fe00}', '\u{fe0f}'), ('\u{fe20}', '\u{fe2f}'), ('\u{e0100}', '\u{e01ef}'),
+];
+
+/// Format chars (Cf), which Java treats as ignorable in identifiers
+const FORMAT_CHARS: &[(char, char)] = &[
+    ('\u{ad}', '\u{ad}'), ('\u{600}', '\u{605}'), ('\u{61c}', '\u{61c}'),
+    ('\u{6dd}', '\u{6dd}'), ('\u{70f}', '\u{70f}'), ('\u{8e2}', '\u{8e2}'),
+    ('\u{180e}', '\u{180e}'), ('\u{200b}', '\u{200f}'), ('\u{202a}', '\u{202e}'),
+    ('\u{2060}', '\u{2064}'), ('\u{2066}', '\u{206f}'), ('\u{feff}', '\u{feff}'),
+    ('\u{fff9}', '\u{fffb}'), ('\u{110bd}', '\u{110bd}'), ('\u{110cd}', '\u{110cd}'),
+    ('\u{13430}', '\u{13438}'), ('\u{1bca0}', '\u{1bca3}'), ('\u{1d173}', '\u{1d17a}'),
+    ('\u{e0001}', '\u{e0001}'), ('\u{e0020}', '\u{e007f}'),
+];
+
+fn in_table(c: char, table: &[(char, char)]) -> bool {
+    table.binary_search_by(|&(lo, hi)| {
+        if hi < c {
+            ::std::cmp::Ordering::Less
+        } else if lo > c {
+            ::std::cmp::Ordering::Greater
+        } else {
+            ::std::cmp::Ordering::Equal
+        }
+    }).is_ok()
+}
+
+/// Java's `Character.isIdentifierIgnorable` - control and format chars which
+/// may appear in identifiers but aren't significant.
+fn is_identifier_ignorable(c: char) -> bool {
+    match c {
+        '\u{0}'..='\u{8}' | '\u{e}'..='\u{1b}' | '\u{7f}'..='\u{9f}' => true,
+        _ => c > '\u{7f}' && in_table(c, FORMAT_CHARS),
+    }
+}
+
+/// Returns true if `c` can start a Java identifier.
+#[inline]
+pub fn is_java_identifier_start(c: char) -> bool {
+    if c.is_ascii() {
+        c.is_ascii_alphabetic() || c == '_' || c == '$'
+    } else {
+        c.is_alphabetic() || in_table(c, CURRENCY_SYMBOLS) || in_table(c, CONNECTOR_PUNCTUATION)
+    }
+}
+
+/// Returns true if `c` can be part of a Java identifier after the first char.
+#[inline]
+pub fn is_java_identifier_part(c: char) -> bool {
+    if c.is_ascii() {
+        c.is_ascii_alphanumeric() || c == '_' || c == '$' || is_identifier_ignorable(c)
+    } else {
+        is_java_identifier_start(c) || c.is_numeric() || in_table(c, COMBINING_MARKS)
+            || is_identifier_ignorable(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_chars() {
+        assert!("aZ_$\u{e9}\u{3b1}\u{4e2d}\u{20ac}\u{203f}".chars().all(is_java_identifier_start));
+        assert!("09\u{300}\u{200b}\u{0}\u{669}".chars().all(is_java_identifier_part));
+        assert!("09\u{300}\u{200b}".chars().all(|c| !is_java_identifier_start(c)));
+        assert!(" .-#@\u{a0}\u{2014}".chars().all(|c| !is_java_identifier_part(c)));
+    }
+}
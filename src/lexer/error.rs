@@ -1,25 +1,34 @@
+use super::{Point, LineCol};
+
 #[derive(Eq, PartialEq, Debug, Clone)]
 pub enum LexErr {
     /// Just an err message
     Raw(String),
-    /// Msg, file, line num
-    Point(String, String, usize),
+    /// Msg, start and end of the offending source
+    Span(String, Point, Point),
+    /// Msg, file, line and column
+    Point(String, String, LineCol),
+    /// Msg, file, line and column, start and end of the offending source
+    SpanPoint(String, String, LineCol, Point, Point),
 }
 
 impl LexErr {
-    pub fn into_point(self, file: String, line_num: usize) -> Self {
+    pub fn into_point(self, file: String, line_col: LineCol) -> Self {
         match self {
-            LexErr::Raw(s) => LexErr::Point(s, file, line_num),
+            LexErr::Raw(s) => LexErr::Point(s, file, line_col),
+            LexErr::Span(s, start, end) => LexErr::SpanPoint(s, file, line_col, start, end),
             _ => panic!("Trying to convert a LexErr::Point into a LexErr::Point!"),
         }
     }
 
-    /// Print this error
+    /// Print this error. Lines and columns are printed 1-based.
     pub fn print_formatted(&self) -> String {
         match *self {
             LexErr::Raw(ref s) => format!("Error: {}", s),
-            LexErr::Point(ref s, ref f, ref l) => format!("Error: {} - {}:{}", s, f, l),
+            LexErr::Span(ref s, ref start, ref end) =>
+                format!("Error: {} - {}..{}", s, start.0, end.0),
+            LexErr::Point(ref s, ref f, ref lc) | LexErr::SpanPoint(ref s, ref f, ref lc, _, _) =>
+                format!("Error: {} - {}:{}:{}", s, f, lc.line + 1, lc.col_utf16 + 1),
         }
     }
 }
-
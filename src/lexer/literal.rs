@@ -0,0 +1,410 @@
+//! Decoding of literal tokens into the values they represent.
+
+use super::{LexErr, Point, Token, TokenType, Translated};
+
+/// The value of a literal token.
+#[derive(PartialEq, Debug, Clone)]
+pub enum LiteralValue {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Char(char),
+    String(String),
+    Bool(bool),
+    Null,
+}
+
+/// Decodes escape sequences in the body of a string, text block or char
+/// literal. `orig` maps an index in `body` to an offset in the original source,
+/// for errors. Text blocks may also escape line terminators.
+fn decode_escapes<F: Fn(usize) -> usize>(body: &str, text_block: bool, orig: F)
+                                         -> Result<String, LexErr> {
+    let mut res = String::with_capacity(body.len());
+    let mut chars = body.char_indices().peekable();
+    while let Some((ix, c)) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        let c = match chars.next() {
+            Some((_, 'b')) => '\u{8}',
+            Some((_, 't')) => '\t',
+            Some((_, 'n')) => '\n',
+            Some((_, 'f')) => '\u{c}',
+            Some((_, 'r')) => '\r',
+            Some((_, 's')) => ' ',
+            Some((_, '"')) => '"',
+            Some((_, '\'')) => '\'',
+            Some((_, '\\')) => '\\',
+            // Line continuation in a text block
+            Some((_, '\n')) if text_block => continue,
+            Some((_, '\r')) if text_block => {
+                if let Some(&(_, '\n')) = chars.peek() { chars.next(); }
+                continue;
+            }
+            // Octal escapes, up to \377
+            Some((_, first @ '0'..='7')) => {
+                let max_len = if first <= '3' { 3 } else { 2 };
+                let mut code = first.to_digit(8).unwrap();
+                for _ in 1..max_len {
+                    match chars.peek() {
+                        Some(&(_, c)) if c.is_digit(8) => {
+                            code = code * 8 + c.to_digit(8).unwrap();
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                ::std::char::from_u32(code).unwrap()
+            }
+            Some((end, c)) => return Err(LexErr::Span(
+                "Invalid escape sequence".to_owned(),
+                Point(orig(ix)), Point(orig(end + c.len_utf8())))),
+            None => return Err(LexErr::Span(
+                "Invalid escape sequence".to_owned(),
+                Point(orig(ix)), Point(orig(body.len())))),
+        };
+        res.push(c);
+    }
+    Ok(res)
+}
+
+/// Strips the incidental whitespace from the content of a text block (the
+/// text between the line terminator after the opening delimiter and the
+/// closing delimiter), as per JLS 3.10.6. Line terminators are normalised to
+/// '\n' and trailing whitespace is removed from each line.
+fn strip_indent(content: &str) -> String {
+    let content = content.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = content.split('\n').collect();
+    let is_blank = |l: &str| l.chars().all(char::is_whitespace);
+    // The last line always counts towards the indent, even when blank, since
+    // it holds the closing delimiter
+    let indent = lines.iter().enumerate()
+        .filter(|&(ix, l)| ix == lines.len() - 1 || !is_blank(l))
+        .map(|(_, l)| l.chars().take_while(|c| c.is_whitespace()).count())
+        .min().unwrap_or(0);
+    let stripped: Vec<&str> = lines.iter().map(|l| if is_blank(l) {
+        ""
+    } else {
+        let start = l.char_indices().nth(indent).map(|(ix, _)| ix).unwrap_or(l.len());
+        l[start..].trim_end()
+    }).collect();
+    stripped.join("\n")
+}
+
+/// Parses the digits of an integer literal, returning None on overflow.
+fn parse_int_digits(digits: &str, radix: u32) -> Option<u64> {
+    digits.chars().filter(|&c| c != '_').try_fold(0u64, |acc, c| {
+        acc.checked_mul(radix as u64)?.checked_add(c.to_digit(radix).unwrap() as u64)
+    })
+}
+
+/// Parses a hex floating point literal (without its suffix), e.g. '0x1.8p1',
+/// rounding to nearest-even at the given precision. `precision` is the number
+/// of significand bits and `min_exp` the exponent of the smallest subnormal,
+/// so 24 and -149 for a float, or 53 and -1074 for a double. The result is
+/// exactly representable in the target type.
+fn parse_hex_float(lit: &str, precision: u32, min_exp: i64) -> f64 {
+    let lit = &lit[2..];
+    let p = lit.find(&['p', 'P'][..]).unwrap();
+    // Huge exponents saturate, keeping their sign so they still overflow or
+    // round to zero
+    let exp_digits = lit[p + 1..].replace('_', "");
+    let saturated = if exp_digits.starts_with('-') { i32::MIN } else { i32::MAX };
+    let mut exp = exp_digits.parse::<i32>().unwrap_or(saturated) as i64;
+    let mut mantissa = 0u64;
+    // Set if any non-zero digits didn't fit in `mantissa`
+    let mut sticky = false;
+    let mut seen_point = false;
+    for c in lit[..p].chars() {
+        match c {
+            '.' => seen_point = true,
+            '_' => (),
+            c => {
+                let digit = c.to_digit(16).unwrap() as u64;
+                // Keep the first 60 or so bits - that's plenty for rounding
+                // to 53 bits, as long as we remember whether anything was lost
+                if mantissa >> 60 == 0 {
+                    mantissa = mantissa * 16 + digit;
+                    if seen_point { exp -= 4; }
+                } else {
+                    sticky |= digit != 0;
+                    if !seen_point { exp += 4; }
+                }
+            }
+        }
+    }
+    if mantissa == 0 {
+        return 0.0;
+    }
+
+    // The exponent of the result's least significant bit, limited by the
+    // subnormals
+    let len = 64 - mantissa.leading_zeros() as i64;
+    let lsb = (exp + len - precision as i64).max(min_exp);
+    let shift = lsb - exp;
+    let significand = if shift <= 0 {
+        mantissa as u128
+    } else if shift > 64 {
+        0
+    } else {
+        let mantissa = mantissa as u128;
+        let (q, rem, half) = (mantissa >> shift, mantissa & ((1 << shift) - 1), 1 << (shift - 1));
+        if rem > half || (rem == half && (sticky || q & 1 == 1)) { q + 1 } else { q }
+    };
+    let exp = if shift <= 0 { exp } else { lsb };
+
+    // Scale in 2 steps so very small results can still reach the subnormals.
+    // The significand has at most `precision` + 1 bits, so this is exact
+    // unless it overflows.
+    let exp = exp.max(i32::MIN as i64).min(i32::MAX as i64) as i32;
+    let half = exp / 2;
+    significand as f64 * 2f64.powi(half) * 2f64.powi(exp - half)
+}
+
+impl LiteralValue {
+    /// Decodes the value of the given literal token. Escape sequences in string
+    /// and char literals are decoded, and numeric literals are range checked.
+    ///
+    /// JLS 3.10.1 only allows the literals 2147483648 and
+    /// 9223372036854775808L as the operand of unary minus. These are decoded
+    /// as the most negative int / long, so it's up to the caller to check the
+    /// literal was negated.
+    pub fn from_token(tok: &Token, src: &str) -> Result<LiteralValue, LexErr> {
+        let raw = tok.val(src);
+        let span_err = |msg: &str| LexErr::Span(msg.to_owned(), tok.start, tok.end);
+        // Unicode escapes in the token need translating first
+        let translated = Translated::new(raw).map_err(|e| match e {
+            LexErr::Span(s, start, end) =>
+                LexErr::Span(s, Point(tok.start.0 + start.0), Point(tok.start.0 + end.0)),
+            e => e,
+        })?;
+        let text: &str = &translated.text;
+        let orig = |ix: usize| tok.start.0 + translated.orig_offset(ix);
+
+        match tok.token_type {
+            TokenType::NullLit => Ok(LiteralValue::Null),
+            TokenType::BoolLit => Ok(LiteralValue::Bool(text == "true")),
+            TokenType::StringLit =>
+                Ok(LiteralValue::String(decode_escapes(&text[1..text.len() - 1], false,
+                                                       |ix| orig(ix + 1))?)),
+            TokenType::TextBlock => {
+                // Skip the opening delimiter and the rest of its line, which
+                // may end in "\r\n", '\n' or a lone '\r'
+                let eol = text.find(&['\n', '\r'][..]).unwrap();
+                let content_start = if text[eol..].starts_with("\r\n") { eol + 2 } else { eol + 1 };
+                let content = &text[content_start..text.len() - 3];
+                // Escapes are interpreted after stripping whitespace, but check
+                // them first so errors can point at the original source
+                decode_escapes(content, true, |ix| orig(ix + content_start))?;
+                Ok(LiteralValue::String(decode_escapes(&strip_indent(content), true, |ix| ix)?))
+            }
+            TokenType::CharLit => {
+                let decoded = decode_escapes(&text[1..text.len() - 1], false, |ix| orig(ix + 1))?;
+                let mut chars = decoded.chars();
+                match (chars.next(), chars.next()) {
+                    // A Java char is a single UTF-16 code unit
+                    (Some(c), None) if c.len_utf16() > 1 =>
+                        Err(span_err("Character literal contains a supplementary character")),
+                    (Some(c), None) => Ok(LiteralValue::Char(c)),
+                    (None, _) => Err(span_err("Empty character literal")),
+                    _ => Err(span_err("Character literal contains more than one character")),
+                }
+            }
+            TokenType::IntLit => {
+                let is_long = text.ends_with('l') || text.ends_with('L');
+                let digits = if is_long { &text[..text.len() - 1] } else { text };
+                let (digits, radix) = match digits.get(..2) {
+                    Some("0x") | Some("0X") => (&digits[2..], 16),
+                    Some("0b") | Some("0B") => (&digits[2..], 2),
+                    _ if digits.len() > 1 && digits.starts_with('0') => (&digits[1..], 8),
+                    _ => (digits, 10),
+                };
+                let val = parse_int_digits(digits, radix);
+                // Decimal literals are limited to the positive range (plus the
+                // magnitude of the minimum value), others may use all the bits
+                let max = match (is_long, radix) {
+                    (false, 10) => 1 << 31,
+                    (false, _) => u32::MAX as u64,
+                    (true, 10) => 1 << 63,
+                    (true, _) => u64::MAX,
+                };
+                match (val, is_long) {
+                    (Some(v), false) if v <= max => Ok(LiteralValue::Int(v as u32 as i32)),
+                    (Some(v), true) if v <= max => Ok(LiteralValue::Long(v as i64)),
+                    (_, false) => Err(span_err("Integer literal is too large for an int")),
+                    (_, true) => Err(span_err("Integer literal is too large for a long")),
+                }
+            }
+            TokenType::FloatLit => {
+                let is_float = text.ends_with('f') || text.ends_with('F');
+                let lit = if is_float || text.ends_with('d') || text.ends_with('D') {
+                    &text[..text.len() - 1]
+                } else { text };
+                let is_hex = lit.starts_with("0x") || lit.starts_with("0X");
+                // Floats are rounded straight to f32 - going via f64 would
+                // round twice
+                let malformed = |_| span_err("Malformed floating point literal");
+                let (value, is_inf, is_zero) = if is_float {
+                    let v = if is_hex {
+                        parse_hex_float(lit, 24, -149) as f32
+                    } else {
+                        lit.replace('_', "").parse::<f32>().map_err(malformed)?
+                    };
+                    (LiteralValue::Float(v), v.is_infinite(), v == 0.0)
+                } else {
+                    let v = if is_hex {
+                        parse_hex_float(lit, 53, -1074)
+                    } else {
+                        lit.replace('_', "").parse::<f64>().map_err(malformed)?
+                    };
+                    (LiteralValue::Double(v), v.is_infinite(), v == 0.0)
+                };
+                // Non-zero literals mustn't round to zero (JLS 3.10.2)
+                let mantissa = if is_hex {
+                    lit[2..].split(&['p', 'P'][..]).next().unwrap()
+                } else {
+                    lit.split(&['e', 'E'][..]).next().unwrap()
+                };
+                let is_zero_lit = mantissa.chars().all(|c| c == '0' || c == '.' || c == '_');
+                if is_inf {
+                    Err(span_err("Floating point literal is too large"))
+                } else if is_zero && !is_zero_lit {
+                    Err(span_err("Floating point literal is too small"))
+                } else {
+                    Ok(value)
+                }
+            }
+            _ => Err(span_err("Expected literal")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    fn decode(src: &str) -> Result<LiteralValue, LexErr> {
+        let tokens = lex(src, "").unwrap();
+        assert_eq!(tokens.len(), 1);
+        LiteralValue::from_token(&tokens[0], src)
+    }
+
+    #[test]
+    fn test_decode_string_and_char() {
+        assert_eq!(decode(r#""Hello,\tworld\n\"\\\101\7\s""#).unwrap(),
+                   LiteralValue::String("Hello,\tworld\n\"\\A\u{7} ".to_owned()));
+        assert_eq!(decode(r#""\u0041\u005c\u005c""#).unwrap(),
+                   LiteralValue::String("A\\".to_owned()));
+        assert_eq!(decode(r"'a'").unwrap(), LiteralValue::Char('a'));
+        assert_eq!(decode(r"'\''").unwrap(), LiteralValue::Char('\''));
+        assert_eq!(decode(r"'\377'").unwrap(), LiteralValue::Char('\u{ff}'));
+        assert_eq!(decode("true").unwrap(), LiteralValue::Bool(true));
+        assert_eq!(decode("null").unwrap(), LiteralValue::Null);
+
+        let src = r#""foo\qbar""#;
+        match decode(src) {
+            Err(LexErr::Span(_, start, end)) => assert_eq!(&src[start.0..end.0], r"\q"),
+            res => panic!("Expected error, got {:?}", res),
+        }
+        assert!(decode("'ab'").is_err());
+        assert!(decode("''").is_err());
+        for src in &["'\u{1f600}'", "'\\uD83D\\uDE00'"] {
+            match decode(src) {
+                Err(LexErr::Span(_, start, end)) => assert_eq!((start.0, end.0), (0, src.len())),
+                res => panic!("Expected error, got {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_text_block() {
+        let src = "\"\"\"\n    <html>\n        <p>Hi\\s</p>   \n\n    </html>\n    \"\"\"";
+        assert_eq!(decode(src).unwrap(),
+                   LiteralValue::String("<html>\n    <p>Hi </p>\n\n</html>\n".to_owned()));
+
+        // Closing delimiter on the last line of content, and a less indented
+        // closing delimiter
+        let src = "\"\"\"\r\n    foo\r\n      bar\"\"\"";
+        assert_eq!(decode(src).unwrap(), LiteralValue::String("foo\n  bar".to_owned()));
+        let src = "\"\"\"\n    foo\n  \"\"\"";
+        assert_eq!(decode(src).unwrap(), LiteralValue::String("  foo\n".to_owned()));
+        // A lone '\r' ends the opening line too, even with '\n's later on
+        let src = "\"\"\"\r  foo\n  bar\"\"\"";
+        assert_eq!(decode(src).unwrap(), LiteralValue::String("foo\nbar".to_owned()));
+
+        // Line continuations and escaped quotes
+        let src = "\"\"\"\n    a \\\n    b \\\"\"\"\n    \"\"\"";
+        assert_eq!(decode(src).unwrap(), LiteralValue::String("a b \"\"\"\n".to_owned()));
+
+        let src = "\"\"\"\n  \\q\"\"\"";
+        match decode(src) {
+            Err(LexErr::Span(_, start, end)) => assert_eq!(&src[start.0..end.0], "\\q"),
+            res => panic!("Expected error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_decode_int() {
+        assert_eq!(decode("1_000").unwrap(), LiteralValue::Int(1000));
+        assert_eq!(decode("0xFF").unwrap(), LiteralValue::Int(255));
+        assert_eq!(decode("017").unwrap(), LiteralValue::Int(15));
+        assert_eq!(decode("0b101").unwrap(), LiteralValue::Int(5));
+        assert_eq!(decode("0xFFFFFFFF").unwrap(), LiteralValue::Int(-1));
+        assert_eq!(decode("2147483647").unwrap(), LiteralValue::Int(i32::MAX));
+        assert_eq!(decode("2147483648").unwrap(), LiteralValue::Int(i32::MIN));
+        assert_eq!(decode("10L").unwrap(), LiteralValue::Long(10));
+        assert_eq!(decode("0xFFFFFFFFFFFFFFFFL").unwrap(), LiteralValue::Long(-1));
+        assert_eq!(decode("9223372036854775808L").unwrap(), LiteralValue::Long(i64::MIN));
+        assert!(decode("2147483649").is_err());
+        assert!(decode("0x1FFFFFFFF").is_err());
+        assert!(decode("9223372036854775809L").is_err());
+        assert!(decode("0x1FFFFFFFFFFFFFFFFL").is_err());
+    }
+
+    #[test]
+    fn test_decode_float() {
+        assert_eq!(decode("1.5").unwrap(), LiteralValue::Double(1.5));
+        assert_eq!(decode("3.f").unwrap(), LiteralValue::Float(3.0));
+        assert_eq!(decode(".25").unwrap(), LiteralValue::Double(0.25));
+        assert_eq!(decode("1e-3").unwrap(), LiteralValue::Double(1e-3));
+        assert_eq!(decode("1_0.5d").unwrap(), LiteralValue::Double(10.5));
+        assert_eq!(decode("0x1.8p1").unwrap(), LiteralValue::Double(3.0));
+        assert_eq!(decode("0x.8p0f").unwrap(), LiteralValue::Float(0.5));
+        assert_eq!(decode("0.0").unwrap(), LiteralValue::Double(0.0));
+        assert!(decode("1e39f").is_err());
+        assert!(decode("1e-50f").is_err());
+        assert!(decode("1e309").is_err());
+
+        // Floats are rounded once, not via a double. The double nearest this
+        // is halfway between two floats, and would round up to the even one.
+        assert_eq!(decode("1.00000017881393432617187499f").unwrap(),
+                   LiteralValue::Float(1.0 + f32::EPSILON));
+        // Hex digits past the first 60 bits still break ties, so these round up
+        assert_eq!(decode("0x1.00000000000008000000000000001p0").unwrap(),
+                   LiteralValue::Double(1.0 + f64::EPSILON));
+        assert_eq!(decode("0x1.000001000000000000001p0f").unwrap(),
+                   LiteralValue::Float(1.0 + f32::EPSILON));
+        // An exact tie rounds to even
+        assert_eq!(decode("0x1.000001p0f").unwrap(), LiteralValue::Float(1.0));
+        assert_eq!(decode("0x1.000003p0f").unwrap(),
+                   LiteralValue::Float(1.0 + 2.0 * f32::EPSILON));
+        // Subnormals round at the smallest subnormal
+        assert_eq!(decode("0x1.8p-149f").unwrap(), LiteralValue::Float(f32::from_bits(2)));
+        assert_eq!(decode("0x1p-1074").unwrap(), LiteralValue::Double(f64::from_bits(1)));
+        assert!(decode("0x1p-1076").is_err());
+        assert!(decode("0x1p128f").is_err());
+        // Exponents too big for an int keep their sign
+        let cases = [("0x1p-99999999999", "small"), ("0x1p-99999999999999999999", "small"),
+                     ("0x1p99999999999999999999f", "large")];
+        for &(src, size) in &cases {
+            match decode(src) {
+                Err(LexErr::Span(msg, _, _)) => assert!(msg.ends_with(size), "{}", msg),
+                res => panic!("Expected error, got {:?}", res),
+            }
+        }
+        assert_eq!(decode("0x1.fffffep127f").unwrap(), LiteralValue::Float(f32::MAX));
+    }
+}
@@ -1,388 +1,162 @@
 mod token;
 mod error;
+mod unicode;
+mod literal;
+mod chars;
+mod source_map;
+mod stream;
+mod scan;
 
-pub use self::token::{Token, TokenType, Point};
+pub use self::token::{Token, TokenType, TokenKind, CommentKind, ContextualKeyword, Point};
 pub use self::error::LexErr;
+pub use self::unicode::Translated;
+pub use self::literal::LiteralValue;
+pub use self::chars::{is_java_identifier_start, is_java_identifier_part};
+pub use self::source_map::{SourceMap, LineCol};
+pub use self::stream::Lexer;
+pub use self::scan::lex_token;
 
-use std::str::CharIndices;
-
-#[inline]
-pub fn try_comment(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    let mut clone = cix.clone();
-    let (start, c) = clone.next().unwrap();
-    match c {
-        '/' => match clone.next() {
-            // /* style comment
-            Some((_, '*')) => {
-                // Consume the //
-                cix.next().unwrap();
-                cix.next().unwrap();
-                let mut end = start + 3;
-                let mut found_asterisk = false;
-                // Now consume until */, using found_asterisk to remember if the
-                // last token was *
-                while let Some((ix, c)) = cix.next() {
-                    end = ix;
-                    if c == '*' {
-                        found_asterisk = true;
-                    } else if c == '/' && found_asterisk {
-                        end += 1;
-                        break;
-                    } else {
-                        found_asterisk = false;
-                    }
-                }
-                Ok(Some(Token::new_comment(start, end)))
-            }
-            // // style comment
-            Some((_, '/')) => {
-                // Consume the //
-                cix.next().unwrap();
-                cix.next().unwrap();
-                let mut end = start + 3;
-                // Now consume until \n or EOF
-                while let Some((ix, c)) = cix.clone().next() {
-                    end = ix;
-                    if c == '\n' { break; }
-                    cix.next().unwrap();
-                }
-                Ok(Some(Token::new_comment(start, end)))
-            }
-            _ => Ok(None),
-        }
-        _ => Ok(None),
-    }
-}
+/// The version of Java being lexed, e.g. `JavaVersion(8)`. This only matters
+/// for the few things whose lexing changed between versions.
+#[derive(Ord, Eq, PartialEq, PartialOrd, Debug, Clone, Copy, Hash)]
+pub struct JavaVersion(pub u32);
 
-/// Checks if s starts with a punctuation char. All punc is just 1 char in this
-/// lang, so just consume 1 char if a punc is found.
-#[inline]
-pub fn try_punc(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    let mut clone = cix.clone();
-    let (ix, c) = clone.next().unwrap();
-    match c {
-        '.' => {
-            match clone.next() {
-                Some((_, '.')) => match clone.next() {
-                    Some((_, '.')) => {
-                        for _ in 0..3 { cix.next().unwrap(); }
-                        Ok(Some(Token::new_punc(ix, ix + 1)))
-                    }
-                    _ => {
-                        cix.next().unwrap();
-                        Ok(Some(Token::new_punc(ix, ix + 1)))
-                    }
-                },
-                _ => {
-                    cix.next().unwrap();
-                    Ok(Some(Token::new_punc(ix, ix + 1)))
-                }
-            }
-        }
-        ':' | ',' | '(' | ')' | '[' | ']' | '{' | '}' | ';' | '@' | '<' | '>' => {
-            cix.next().unwrap();
-            Ok(Some(Token::new_punc(ix, ix + 1)))
-        }
-        _ => Ok(None)
-    }
+impl Default for JavaVersion {
+    /// The latest version we know how to lex
+    fn default() -> JavaVersion { JavaVersion(17) }
 }
 
-#[inline]
-pub fn try_op(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    let mut clone = cix.clone();
-    let (ix, c) = clone.next().unwrap();
-    // Welp, this is pretty fucking messy. Fasterthanregextho.
-    let (tok, consumed) = match c {
-        '~' | '?' | ':' => (Token::new_op(ix, ix+1), 1),
-        '=' | '/' | '*' | '^' | '%' | '!' => match clone.next() {
-            Some((_, '=')) => (Token::new_op(ix, ix+2), 2),
-            _ => (Token::new_op(ix, ix+1), 1)
-        },
-        '+' => match clone.next() {
-            Some((_, '=')) | Some((_, '+')) => (Token::new_op(ix, ix+2), 2),
-            _ => (Token::new_op(ix, ix+1), 1),
-        },
-        '-' => match clone.next() {
-            Some((_, '=')) | Some((_, '-')) | Some((_, '>')) => (Token::new_op(ix, ix+2), 2),
-            _ => (Token::new_op(ix, ix+1), 1),
-        },
-        '>' => match clone.next() {
-            Some((_, '=')) => (Token::new_op(ix, ix+2), 2),
-            Some((_, '>')) => match clone.next() {
-                Some((_, '>')) => match clone.next() {
-                    Some((_, '=')) => (Token::new_op(ix, ix+4), 4),
-                    _ => (Token::new_op(ix, ix+3), 3),
-                }
-                Some((_, '=')) => (Token::new_op(ix, ix+3), 3),
-                _ => (Token::new_op(ix, ix+2), 2),
-            },
-            _ => (Token::new_op(ix, ix+1), 1)
-        }
-        '<' => {
-            match clone.next() {
-                Some((_, '=')) => (Token::new_op(ix, ix+2), 2),
-                Some((_, '<')) => match clone.next() {
-                    Some((_, '=')) => (Token::new_op(ix, ix+3), 3),
-                    _ => (Token::new_op(ix, ix+2), 2),
-                }
-                _ => (Token::new_op(ix, ix+1), 1),
-            }
-        }
-        '&' => {
-            match clone.next() {
-                Some((_, '&')) | Some((_, '=')) => (Token::new_op(ix, ix+2), 2),
-                _ => (Token::new_op(ix, ix+1), 1),
-            }
-        }
-        '|' => {
-            match clone.next() {
-                Some((_, '|')) | Some((_, '=')) => (Token::new_op(ix, ix+2), 2),
-                _ => (Token::new_op(ix, ix+1), 1),
-            }
-        }
-        _ => return Ok(None)
-    };
-    for _ in 0..consumed { cix.next(); }
-    Ok(Some(tok))
+/// Lex the given source as the latest version of Java. Unicode escapes are
+/// translated before lexing, but tokens and errors always point into the
+/// original source. Only significant tokens are returned, with whitespace and
+/// comments attached to them as trivia.
+pub fn lex(src: &str, file: &str) -> Result<Vec<Token>, LexErr> {
+    lex_with_version(src, file, JavaVersion::default())
 }
 
-#[inline]
-pub fn try_key(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    let (start, _) = cix.clone().next().unwrap();
-    const KEYS : [&str; 50] =
-        ["abstract", "continue", "for", "new", "switch", "assert", "default",
-         "goto", "package", "synchronized", "boolean", "do", "if", "private", "this",
-         "break", "double", "implements", "protected", "throw", "byte", "else",
-         "import", "public", "throws", "case", "enum", "instanceof", "return",
-         "transient", "catch", "extends", "int", "short", "try", "char", "final",
-         "interface", "static", "void", "class", "finally", "long", "strictfp",
-         "volatile", "const", "float", "native", "super", "while"];
-    for k in KEYS.iter() {
-        if cix.as_str().starts_with(k) {
-            return match cix.clone().skip(k.len()).next() {
-                None => {
-                    for _ in 0..k.len() { cix.next(); } // Consume
-                    Ok(Some(Token::new_key(start, start + k.len())))
-                }
-                Some((_, c)) if !c.is_alphanumeric() => {
-                    for _ in 0..k.len() { cix.next(); } // Consume
-                    Ok(Some(Token::new_key(start, start + k.len())))
-                }
-                _ => {
-                    Ok(None)
-                }
-            }
-        }
+/// Lex the given source as the given version of Java.
+pub fn lex_with_version(src: &str, file: &str, version: JavaVersion)
+                        -> Result<Vec<Token>, LexErr> {
+    if src.is_empty() {
+        return Err(LexErr::Raw("File is empty.".to_owned()));
     }
-    Ok(None)
-}
-
-#[inline]
-pub fn try_char_lit(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    if cix.clone().next().unwrap().1 == '\'' {
-        let (start, _) = cix.next().unwrap();
-        // Keep consuming until we hit another unescaped "
-        let mut escaped = false;
-        let mut end = None;
-        while let Some((ix, c)) = cix.next() {
-            if escaped {
-                escaped = false;
-                continue;
-            } else if c == '\\' {
-                escaped = true;
-                continue;
-            } else if c == '\'' {
-                end = Some(ix + 1);
-                break;
-            }
-        }
-        match end {
-            None => Err(LexErr::Raw("Unexpected EOF in char literal".to_owned())),
-            Some(end) => Ok(Some(Token::new_string_lit(start, end)))
-        }
-    } else { Ok(None) }
+    Lexer::with_version(src, file, version).collect()
 }
 
-pub fn try_string_lit(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    if cix.clone().next().unwrap().1 == '"' {
-        let (start, _) = cix.next().unwrap();
-        // Keep consuming until we hit another unescaped "
-        let mut escaped = false;
-        let mut end = None;
-        while let Some((ix, c)) = cix.next() {
-            if escaped {
-                escaped = false;
-                continue;
-            } else if c == '\\' {
-                escaped = true;
-                continue;
-            } else if c == '"' {
-                end = Some(ix + 1);
-                break;
-            }
-        }
-        match end {
-            None => Err(LexErr::Raw("Unexpected EOF in string literal".to_owned())),
-            Some(end) => Ok(Some(Token::new_string_lit(start, end)))
-        }
-    } else { Ok(None) }
+/// Lex the given source as the latest version of Java, including whitespace
+/// and comments as tokens of their own.
+pub fn lex_raw(src: &str, file: &str) -> Result<Vec<Token>, LexErr> {
+    Lexer::new(src, file).raw().collect()
 }
 
-#[inline]
-pub fn try_num_lit(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    let mut clone = cix.clone();
-    let (start, first) = clone.next().unwrap();
-    if first.is_digit(10) || first == '-' {
-        // Consume until we hit a non-digit
-        let mut num_consumed = 1;
-        let mut consumed_decimal_point = false;
-        let mut end = start;
-        while let Some((ix, c)) = clone.next() {
-            if c.is_alphabetic() {
-                return Err(LexErr::Raw("Identifier cannot start with a number".to_owned()));
-            } else if c == '.' && consumed_decimal_point {
-                return Err(LexErr::Raw("Error: num literal contains more than 1 decimal place".to_owned()));
-            } else if !c.is_digit(10) && c != '.' {
-                break;
-            } else if c == '.' {
-                consumed_decimal_point = true;
-            }
-            num_consumed += 1;
-            end = ix;
-        }
-        for _ in 0..num_consumed { cix.next(); } // Advance the iterator
-        if consumed_decimal_point {
-            Ok(Some(Token::new_float_lit(start, end+1)))
-        } else {
-            Ok(Some(Token::new_int_lit(start, end+1)))
-        }
-    } else { Ok(None) }
+/// Lex the given source as the latest version of Java, without stopping at
+/// errors. See `lex_tolerant_with_version`.
+pub fn lex_tolerant(src: &str, file: &str) -> (Vec<Token>, Vec<LexErr>) {
+    lex_tolerant_with_version(src, file, JavaVersion::default())
 }
 
-#[inline]
-pub fn try_null_lit(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    let start = cix.clone().next().unwrap().0;
-    if cix.as_str().starts_with("null") {
-        return match cix.clone().skip(4).next() {
-            Some((_, c)) if !c.is_alphanumeric() => {
-                for _ in 0..4 { cix.next(); } // Consume
-                Ok(Some(Token::new_null_lit(start, start + 4)))
-            }
-            None => {
-                for _ in 0..4 { cix.next(); } // Consume
-                Ok(Some(Token::new_null_lit(start, start + 4)))
+/// Lex the given source as the given version of Java, without stopping at
+/// errors. Source which can't be lexed becomes a `TokenType::Error` token and
+/// an error in the returned list, then lexing resumes just after it.
+pub fn lex_tolerant_with_version(src: &str, file: &str, version: JavaVersion)
+                                 -> (Vec<Token>, Vec<LexErr>) {
+    let mut lexer = Lexer::with_version(src, file, version);
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(res) = lexer.next() {
+        match res {
+            Ok(tok) => tokens.push(tok),
+            Err(e) => {
+                if let LexErr::SpanPoint(_, _, _, start, end) = e {
+                    // Errors inside a token, like a bad escape in a string
+                    // literal, don't need an error token of their own
+                    let in_token = match lexer.peek() {
+                        Some(Ok(tok)) => tok.start <= start && end <= tok.end,
+                        _ => false,
+                    };
+                    if !in_token {
+                        // Any trivia before the error belongs to it
+                        let full_start = tokens.last().map_or(Point(0), |t: &Token| t.full_end);
+                        tokens.push(Token { full_start, .. Token::new_error(start.0, end.0) });
+                    }
+                }
+                errors.push(e);
             }
-            _ => Ok(None)
         }
-    } else {
-        Ok(None)
     }
-}
-
-#[inline]
-pub fn try_bool_lit(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    let start = cix.clone().next().unwrap().0;
-    let as_str = cix.as_str();
-    if let Some((tok, to_consume)) = if as_str.starts_with("true") {
-        let len = "true".len();
-        Some((Token::new_bool_lit(start, start + len), len))
-    } else if as_str.starts_with("false") {
-        let len = "false".len();
-        Some((Token::new_bool_lit(start, start + len), len))
-    } else { None } {
-        for _ in 0..to_consume { cix.next(); }
-        Ok(Some(tok))
-    } else {
-        Ok(None)
+    // Trivia after an error at the end of the file isn't attached to anything
+    if let Some(tok) = tokens.last_mut() {
+        tok.full_end = Point(src.len());
     }
+    (tokens, errors)
 }
 
-#[inline]
-pub fn try_ident(cix: &mut CharIndices) -> Result<Option<Token>, LexErr> {
-    let mut clone = cix.clone();
-    let (start, first) = clone.next().unwrap();
-    if first.is_alphabetic() {
-        // Consume until we hit a non-alphanumeric
-        let mut num_consumed = 1;
-        let mut end = start + 1;
-        while let Some((ix, c)) = clone.next() {
-            if !(c.is_alphanumeric() || c == '_') { break; }
-            num_consumed += 1;
-            end = ix + 1;
-        }
-        for _ in 0..num_consumed { cix.next(); } // Advance the iterator
-        Ok(Some(Token::new_ident(start, end)))
-
-    } else { Ok(None) }
-}
+#[cfg(test)]
+mod test {
+    use std;
 
-pub fn lex_token(cix: &mut CharIndices) -> Result<Token, LexErr> {
-    if let Some(tok) = try_comment(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_op(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_punc(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_num_lit(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_bool_lit(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_null_lit(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_key(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_char_lit(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_string_lit(cix)? {
-        Ok(tok)
-    } else if let Some(tok) = try_ident(cix)? {
-        Ok(tok)
-    } else {
-        return Err(LexErr::Raw("Unknown token".to_owned()));
+    #[test]
+    fn test_lex() {
+        let src = std::str::from_utf8(include_bytes!("../../res/test-src/com/tom/Main.java"))
+            .unwrap();
+        let res = super::lex(src, "com/tom/Main.java").unwrap();
+        assert!(!res.is_empty());
     }
-}
 
-pub fn lex(src: &str, file: &str) -> Result<Vec<Token>, LexErr> {
-    if src.is_empty() {
-        return Err(LexErr::Raw("File is empty.".to_owned()));
+    #[test]
+    fn test_lex_text_block() {
+        let src = "String s = \"\"\"  \n    Hello, \\\"\"\"\n    \"world\\\"\"\"\";";
+        let tokens = super::lex(src, "").unwrap();
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[3].token_type, super::TokenType::TextBlock);
+        assert_eq!(tokens[3].val(src), &src[11..src.len() - 1]);
+
+        assert!(super::lex("\"\"\"foo\"\"\"", "").is_err());
+        assert!(super::lex("\"\"\"\nfoo\"\"", "").is_err());
     }
 
-    let mut tokens = Vec::new();
-    let mut char_ix = src.char_indices();
-    let mut line_num = 0;
-
-    while !char_ix.as_str().is_empty() {
-        // Check if this is a newline, and increment line_num
-        if char_ix.clone().next().unwrap().1 == '\n' {
-            char_ix.next();
-            line_num += 1;
-            continue;
-        } else if char_ix.clone().next().unwrap().1.is_whitespace() {
-            // Just consume whitespace
-            char_ix.next();
-            continue;
-        }
+    #[test]
+    fn test_lex_comments() {
+        use super::CommentKind::{Line, Block, Doc};
+        use super::TokenType::Comment;
+        let src = "// line\r\n/* block */ /** doc */ /**/ /***/ a // at EOF";
+        let tokens: Vec<_> = super::lex_raw(src, "").unwrap().into_iter()
+            .filter(|t| t.token_type != super::TokenType::Whitespace).collect();
+        let vals: Vec<_> = tokens.iter().map(|t| (t.token_type, t.val(src))).collect();
+        assert_eq!(vals, vec![(Comment(Line), "// line"), (Comment(Block), "/* block */"),
+                              (Comment(Doc), "/** doc */"), (Comment(Block), "/**/"),
+                              (Comment(Doc), "/***/"), (super::TokenType::Ident, "a"),
+                              (Comment(Line), "// at EOF")]);
+        assert!(tokens[0].is_comment() && !tokens[5].is_comment());
 
-        // Try lex a token
-        match lex_token(&mut char_ix) {
-            Ok(tok) => tokens.push(tok),
-            Err(e) => return Err(e.into_point(file.to_string(), line_num)),
+        let src = "a /* never closed\n b";
+        match super::lex(src, "") {
+            Err(super::LexErr::SpanPoint(_, _, _, start, end)) =>
+                assert_eq!((start.0, end.0), (2, src.len())),
+            res => panic!("Expected error, got {:?}", res),
         }
     }
 
-    Ok(tokens)
-}
-
-#[cfg(test)]
-mod test {
-    use std;
-
     #[test]
-    fn test_lex() {
-        let src = std::str::from_utf8(include_bytes!("../../res/test-src/com/tom/Main.java"))
-            .unwrap();
-        let res = super::lex(src, "com/tom/Main.java").unwrap();
-        assert!(res.len() > 0);
+    fn test_lex_trivia() {
+        let src = "  /** Doc */\n  int a; // A\r\n\n  int b = \\u0031;\n  // The end\n";
+        let tokens = super::lex(src, "").unwrap();
+        assert_eq!(tokens.len(), 8);
+        assert_eq!(tokens[0].leading_trivia(src), "  /** Doc */\n  ");
+        assert_eq!(tokens[2].trailing_trivia(src), " // A\r\n");
+        assert_eq!(tokens[3].leading_trivia(src), "\n  ");
+        assert_eq!(tokens[6].val(src), "\\u0031");
+        assert_eq!(tokens[7].trailing_trivia(src), "\n  // The end\n");
+        let full: String = tokens.iter().map(|t| t.full_val(src)).collect();
+        assert_eq!(full, src);
+        let raw: String = super::lex_raw(src, "").unwrap().iter().map(|t| t.val(src)).collect();
+        assert_eq!(raw, src);
+
+        // Tolerant lexing is lossless too
+        let src = "a # b \"c\n  ";
+        let (tokens, _) = super::lex_tolerant(src, "");
+        let full: String = tokens.iter().map(|t| t.full_val(src)).collect();
+        assert_eq!(full, src);
     }
 
     #[test]
@@ -391,15 +165,172 @@ mod test {
             [">>>=", ">>=", "<<=", ">>>", "%=", "^=", "=", "&=", "/=",
             "*=", "-=", "+=", ">>", "<<", "--", "++", "||", "&&", "!=",
             "<=", ">=", "==", "->", "%", "^", "|", "&", "/", "*", "-", "+",
-            ":", "?", "~", "!", "<", "=", ">"].len();
+            ":", "::", "?", "~", "!", "<", "=", ">"].len();
         let ops = ">>>=\n>>=\n<<=\n>>>\n%=\n^=\n=\n&=\n/=\n\n*=\n-=\n+=\n>>\n<<".to_owned()
             + "\n--\n++\n||\n&&\n!=\n\n<=\n>=\n==\n->\n%\n^\n|\n&\n/\n*\n-\n+"
-            + "\n\n:\n?\n~\n!\n<\n=\n>";
+            + "\n\n:\n::\n?\n~\n!\n<\n=\n>";
 
         let tokens = super::lex(&ops, "").unwrap();
         assert_eq!(tokens.len(), num_ops);
         assert!(tokens.iter().all(|t| t.token_type == super::TokenType::Op));
     }
+
+    #[test]
+    fn test_lex_kinds() {
+        use super::TokenKind::*;
+        let src = "public void f(int... xs) { x >>>= y::z; }";
+        let tokens = super::lex(src, "").unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![Public, Void, Ident, LParen, Int, Ellipsis, Ident, RParen,
+                               LBrace, Ident, GtGtGtEq, Ident, ColonColon, Ident, Semi, RBrace]);
+        assert_eq!(tokens[5].val(src), "...");
+        assert_eq!(super::TokenKind::from_keyword("while"), Some(While));
+        assert_eq!(super::TokenKind::from_symbol(">>="), Some(GtGtEq));
+        assert_eq!(GtGtEq.split_gt(), Some(GtEq));
+    }
+
+    #[test]
+    fn test_lex_num_lit() {
+        use super::TokenType::{IntLit, FloatLit};
+        let lits = [
+            ("0", IntLit), ("123", IntLit), ("1_000_000", IntLit), ("10L", IntLit),
+            ("0xFF", IntLit), ("0XdeadBEEFl", IntLit), ("0x7fff_ffff", IntLit),
+            ("017", IntLit), ("0_7", IntLit), ("0b1010", IntLit), ("0B1_0L", IntLit),
+            ("3.f", FloatLit), (".2f", FloatLit), ("1.5", FloatLit), ("1e-9", FloatLit),
+            ("1E+10d", FloatLit), ("2f", FloatLit), ("09.5", FloatLit), ("1_0.0_1", FloatLit),
+            ("0x1.8p1", FloatLit), ("0x.8P-2f", FloatLit), ("0x1p3", FloatLit),
+        ];
+        for &(src, ty) in lits.iter() {
+            let tokens = super::lex(src, "").unwrap();
+            assert_eq!(tokens.len(), 1, "{} should lex as one token", src);
+            assert_eq!(tokens[0].token_type, ty, "{} lexed with wrong type", src);
+            assert_eq!(tokens[0].val(src), src);
+        }
+
+        let src = "a-1";
+        let tokens = super::lex(src, "").unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[1].val(src), "-");
+        assert_eq!(tokens[2].val(src), "1");
+    }
+
+    #[test]
+    fn test_lex_bad_num_lit() {
+        let bad = ["0x", "0b", "0b102", "1_", "1__", "0x_1", "1._5", "1e", "1e+",
+                   "09", "08L", "1.5L", "0x1.8", "123abc"];
+        for src in bad.iter() {
+            match super::lex(src, "") {
+                Err(super::LexErr::SpanPoint(_, _, _, start, end)) => {
+                    assert_eq!(start.0, 0);
+                    assert_eq!(end.0, src.len(), "Bad span for {}", src);
+                }
+                res => panic!("{} should fail to lex, got {:?}", src, res),
+            }
+        }
+    }
+
+    #[test]
+    fn test_lex_err_position() {
+        let src = "int a;\n/* multi\nline */ String \u{e9} = \"foo";
+        match super::lex(src, "Foo.java") {
+            Err(ref e @ super::LexErr::SpanPoint(..)) =>
+                assert_eq!(e.print_formatted(), "Error: Unexpected EOF in string literal - Foo.java:3:20"),
+            res => panic!("Expected error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_lex_tolerant() {
+        use super::TokenType::{Ident, Op, Punc, StringLit, Error};
+        let src = "a # b\n\"foo\nc = \"bar\";\nint \\u00g1 = 0x;\n\"baz";
+        let (tokens, errors) = super::lex_tolerant(src, "Foo.java");
+        let vals: Vec<_> = tokens.iter().map(|t| (t.token_type, t.val(src))).collect();
+        assert_eq!(&vals[..7], &[(Ident, "a"), (Error, "#"), (Ident, "b"), (Error, "\"foo"),
+                                 (Ident, "c"), (Op, "="), (StringLit, "\"bar\"")]);
+        assert_eq!(&vals[9..], &[(Error, "\\u00g1"), (Op, "="), (Error, "0x"), (Punc, ";"),
+                                 (Error, "\"baz")]);
+        let msgs: Vec<_> = errors.iter().map(|e| e.print_formatted()).collect();
+        assert_eq!(msgs, vec!["Error: Unknown character '#' - Foo.java:1:3",
+                              "Error: Line terminator in string literal - Foo.java:2:1",
+                              "Error: Malformed unicode escape - Foo.java:4:5",
+                              "Error: Expected hex digits in numeric literal - Foo.java:4:14",
+                              "Error: Unexpected EOF in string literal - Foo.java:5:1"]);
+
+        // Non-ASCII chars just after '\u' are part of the malformed escape
+        let src = "\\u\u{20ac}\u{e9} x";
+        let (tokens, errors) = super::lex_tolerant(src, "");
+        assert_eq!(tokens.iter().map(|t| t.val(src)).collect::<Vec<_>>(), vec![src]);
+        assert_eq!(errors.len(), 1);
+
+        // As are the ends of escapes cut short by the end of the file
+        let src = "9\\u";
+        let (tokens, errors) = super::lex_tolerant(src, "");
+        assert_eq!(tokens.iter().map(|t| t.val(src)).collect::<Vec<_>>(), vec!["9", "\\u"]);
+        assert_eq!(errors.len(), 1);
+
+        // Valid source lexes the same either way
+        let src = "class A { String s = \"\\u0041\"; }";
+        assert_eq!(super::lex_tolerant(src, ""), (super::lex(src, "").unwrap(), vec![]));
+    }
+
+    #[test]
+    fn test_lex_ident() {
+        use super::TokenType::{Ident, Key, BoolLit};
+        let src = "_field $proxy $1 int_x truely \u{e9}t\u{e9} \u{4e2d}\u{6587} a\u{300}b int _";
+        let tokens = super::lex(src, "").unwrap();
+        let types: Vec<_> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![Ident, Ident, Ident, Ident, Ident, Ident, Ident, Ident, Key, Key]);
+        assert_eq!(tokens[5].val(src), "\u{e9}t\u{e9}");
+        assert_eq!(tokens[7].val(src), "a\u{300}b");
+
+        // '_' was an identifier before Java 9
+        let tokens = super::lex_with_version("_ true", "", super::JavaVersion(8)).unwrap();
+        assert_eq!(tokens[0].token_type, Ident);
+        assert_eq!(tokens[1].token_type, BoolLit);
+    }
+
+    #[test]
+    fn test_lex_contextual_key() {
+        use super::ContextualKeyword::*;
+        let src = "var yield record sealed non-sealed permits module open requires exports \
+                   opens uses provides to with transitive variable non - sealed";
+        let tokens = super::lex(src, "").unwrap();
+        assert!(tokens.iter().filter(|t| t.kind != super::TokenKind::Minus)
+                .all(|t| t.token_type == super::TokenType::Ident));
+        let keys: Vec<_> = tokens.iter().map(|t| t.contextual).collect();
+        assert_eq!(&keys[..18], &[Some(Var), Some(Yield), Some(Record), Some(Sealed),
+                                  Some(NonSealed), None, Some(Sealed), Some(Permits),
+                                  Some(Module), Some(Open), Some(Requires), Some(Exports),
+                                  Some(Opens), Some(Uses), Some(Provides), Some(To), Some(With),
+                                  Some(Transitive)]);
+        // 'non-sealed' is 3 tokens, and 'non' is only tagged if there's no space
+        assert_eq!(tokens[4].val(src), "non");
+        assert_eq!(tokens[5].val(src), "-");
+        assert_eq!(&keys[18..], &[None, None, None, Some(Sealed)]);
+        assert!(tokens[0].is_contextual_key(Var));
+    }
+
+    #[test]
+    fn test_lex_unicode_escapes() {
+        // 'int a = 1;' with the 'a' and '=' escaped
+        let src = "int \\u0061 \\u003D 1;";
+        let tokens = super::lex(src, "").unwrap();
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[1].token_type, super::TokenType::Ident);
+        assert_eq!(tokens[1].val(src), "\\u0061");
+        assert_eq!(tokens[2].val(src), "\\u003D");
+        assert_eq!(tokens[3].val(src), "1");
+
+        // Errors point into the original source
+        let src = "int a\\u0062 = 09;";
+        match super::lex(src, "") {
+            Err(super::LexErr::SpanPoint(_, _, lc, start, end)) => {
+                assert_eq!(&src[start.0..end.0], "09");
+                assert_eq!((lc.line, lc.col_utf8), (0, 14));
+            }
+            res => panic!("Expected error, got {:?}", res),
+        }
+    }
 }
 
 #[cfg(feature = "bench")]
@@ -425,4 +356,59 @@ mod benches {
         "#;
         b.iter(|| test::black_box(super::lex(java_code, "")));
     }
+
+    /// About 100KB of typical source, to measure throughput in MB/s
+    fn large_src() -> String {
+        let class = r#"
+        /**
+         * A class with a bit of everything in it.
+         */
+        @SuppressWarnings("unchecked")
+        public final class Counter<T extends Comparable<T>> implements Iterable<T> {
+            private static final long serialVersionUID = 0x1234_5678L;
+            private final java.util.Map<T, Integer> counts = new java.util.HashMap<>();
+
+            public synchronized int increment(T key, int by) throws IllegalStateException {
+                if (key == null || by <= 0) { throw new IllegalArgumentException("key"); }
+                int count = counts.getOrDefault(key, 0) + by; // The new count
+                counts.put(key, count >>> 0);
+                return count;
+            }
+
+            @Override
+            public java.util.Iterator<T> iterator() {
+                double total = 0.0d;
+                for (int i = 0; i < counts.size(); i++) { total += i * 1.5e3 / 'c'; }
+                return counts.keySet().iterator();
+            }
+        }
+        "#;
+        class.repeat(100_000 / class.len() + 1)
+    }
+
+    #[bench]
+    fn lex_large(b: &mut Bencher) {
+        let src = large_src();
+        b.bytes = src.len() as u64;
+        b.iter(|| test::black_box(super::lex(&src, "")));
+    }
+
+    #[bench]
+    fn lex_raw_large(b: &mut Bencher) {
+        let src = large_src();
+        b.bytes = src.len() as u64;
+        b.iter(|| test::black_box(super::lex_raw(&src, "")));
+    }
+
+    /// Mostly keywords and identifiers which look like keywords
+    #[bench]
+    fn lex_keywords(b: &mut Bencher) {
+        let src = "public protected private abstract static final synchronized native \
+                   transient volatile strictfp interface implements instanceof finally \
+                   publicity protection privately finals integer doubled throwing \
+                   record sealed permits var yield true false null "
+            .repeat(1000);
+        b.bytes = src.len() as u64;
+        b.iter(|| test::black_box(super::lex(&src, "")));
+    }
 }
@@ -0,0 +1,450 @@
+//! Lexes a single token. The first byte of the token picks the only scanner
+//! which can match it through a 256-entry table, and everything is scanned
+//! bytewise - chars are only decoded for non-ASCII identifiers and errors.
+//!
+//! Multi-byte UTF-8 sequences only contain bytes >= 0x80, so they can never be
+//! mistaken for the ASCII quotes, operators and line terminators looked for
+//! here.
+
+use super::{is_java_identifier_part, is_java_identifier_start, CommentKind, ContextualKeyword,
+            JavaVersion, LexErr, Point, Token, TokenKind};
+
+/// What a token starting with some byte can be
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Start {
+    Ident,
+    Digit,
+    /// '.', '..' or '...', or a float like '.5'
+    Dot,
+    Quote,
+    Apos,
+    /// A comment or a division operator
+    Slash,
+    Op,
+    /// A single byte punctuator
+    Punc(TokenKind),
+    Whitespace,
+    /// The first byte of a multi-byte char
+    NonAscii,
+    Unknown,
+}
+
+const fn start_of(b: u8) -> Start {
+    match b {
+        b'a'..=b'z' | b'A'..=b'Z' | b'_' | b'$' => Start::Ident,
+        b'0'..=b'9' => Start::Digit,
+        b'.' => Start::Dot,
+        b'"' => Start::Quote,
+        b'\'' => Start::Apos,
+        b'/' => Start::Slash,
+        b'~' | b'?' | b':' | b'=' | b'!' | b'+' | b'-' | b'*' | b'%' | b'^' | b'&' | b'|'
+            | b'<' | b'>' => Start::Op,
+        b',' => Start::Punc(TokenKind::Comma),
+        b'(' => Start::Punc(TokenKind::LParen),
+        b')' => Start::Punc(TokenKind::RParen),
+        b'[' => Start::Punc(TokenKind::LBracket),
+        b']' => Start::Punc(TokenKind::RBracket),
+        b'{' => Start::Punc(TokenKind::LBrace),
+        b'}' => Start::Punc(TokenKind::RBrace),
+        b';' => Start::Punc(TokenKind::Semi),
+        b'@' => Start::Punc(TokenKind::At),
+        // Only space, tab, form feed and line terminators (JLS 3.6)
+        b' ' | b'\t' | b'\x0c' | b'\n' | b'\r' => Start::Whitespace,
+        0x80..=0xff => Start::NonAscii,
+        _ => Start::Unknown,
+    }
+}
+
+/// The same as is_java_identifier_part for ASCII. Bytes of multi-byte chars
+/// are never set, so identifiers drop to the slow path at the first one.
+const fn is_ascii_ident_part(b: u8) -> bool {
+    matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'$'
+             | 0x00..=0x08 | 0x0e..=0x1b | 0x7f)
+}
+
+const fn build_start_table() -> [Start; 256] {
+    let mut table = [Start::Unknown; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = start_of(i as u8);
+        i += 1;
+    }
+    table
+}
+
+const fn build_ident_part_table() -> [bool; 256] {
+    let mut table = [false; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = is_ascii_ident_part(i as u8);
+        i += 1;
+    }
+    table
+}
+
+static START: [Start; 256] = build_start_table();
+static IDENT_PART: [bool; 256] = build_ident_part_table();
+
+/// Returns the length of the whitespace at the start of `s`. Other Unicode
+/// whitespace, like a non-breaking space, isn't whitespace in Java.
+#[inline]
+pub fn scan_whitespace(s: &str) -> usize {
+    let b = s.as_bytes();
+    let mut len = 0;
+    while len < b.len() && START[b[len] as usize] == Start::Whitespace { len += 1; }
+    len
+}
+
+/// Lexes the token at the start of `s`, which mustn't be empty or start with
+/// whitespace. The token is relative to the start of `s`. Errors are always
+/// spans starting at 0 and covering at least the first char, and the end of
+/// the span is where lexing should resume.
+pub fn lex_token(s: &str, version: JavaVersion) -> Result<Token, LexErr> {
+    let b = s.as_bytes();
+    match START[b[0] as usize] {
+        Start::Ident => Ok(scan_ident(s, version)),
+        Start::Digit => scan_num_lit(b),
+        Start::Dot if b.len() > 1 && b[1].is_ascii_digit() => scan_num_lit(b),
+        Start::Dot if b.starts_with(b"...") => Ok(Token::new_punc(0, 3, TokenKind::Ellipsis)),
+        Start::Dot => Ok(Token::new_punc(0, 1, TokenKind::Dot)),
+        Start::Quote if b.starts_with(b"\"\"\"") => scan_text_block(b),
+        Start::Quote => Ok(Token::new_string_lit(0, scan_quoted(b, b'"', "string")?)),
+        Start::Apos => Ok(Token::new_char_lit(0, scan_quoted(b, b'\'', "char")?)),
+        Start::Slash if b.starts_with(b"/*") => scan_block_comment(s),
+        Start::Slash if b.starts_with(b"//") => {
+            // Runs until the end of the line or EOF
+            let len = b.iter().position(|&c| c == b'\n' || c == b'\r').unwrap_or(b.len());
+            Ok(Token::new_comment(0, len, CommentKind::Line))
+        }
+        Start::Slash | Start::Op => {
+            let (len, kind) = scan_op(b);
+            Ok(Token::new_op(0, len, kind))
+        }
+        Start::Punc(kind) => Ok(Token::new_punc(0, 1, kind)),
+        Start::NonAscii if is_java_identifier_start(s.chars().next().unwrap()) =>
+            Ok(scan_ident(s, version)),
+        Start::NonAscii | Start::Whitespace | Start::Unknown => {
+            let c = s.chars().next().unwrap();
+            Err(LexErr::Span(format!("Unknown character '{}'", c), Point(0), Point(c.len_utf8())))
+        }
+    }
+}
+
+/// Returns the end of the identifier chars starting at `ix`.
+#[inline]
+fn scan_ident_part(s: &str, ix: usize) -> usize {
+    let b = s.as_bytes();
+    let mut end = ix;
+    while end < b.len() && IDENT_PART[b[end] as usize] { end += 1; }
+    if end < b.len() && b[end] >= 0x80 {
+        // Carry on char by char, which handles ASCII after this too
+        end += s[end..].find(|c: char| !is_java_identifier_part(c)).unwrap_or(s.len() - end);
+    }
+    end
+}
+
+/// Lexes an identifier, keyword, boolean or null literal. The first char has
+/// already been checked to be an identifier start. Identifiers which may be
+/// contextual keywords are tagged as such.
+fn scan_ident(s: &str, version: JavaVersion) -> Token {
+    let first_len = s.chars().next().unwrap().len_utf8();
+    let end = scan_ident_part(s, first_len);
+
+    let word = &s[..end];
+    match word {
+        "true" | "false" => Token::new_bool_lit(0, end),
+        "null" => Token::new_null_lit(0, end),
+        // '_' is only a keyword since Java 9, before that it's an identifier
+        "_" if version < JavaVersion(9) => Token::new_ident(0, end),
+        // 'non-sealed' is lexed as 'non', '-' and 'sealed', since outside of
+        // modifiers it's a subtraction. The 'non' is tagged if it's written
+        // with no space before '-sealed', and the parser joins them up.
+        "non" if s[end..].starts_with("-sealed")
+            && scan_ident_part(s, end + "-sealed".len()) == end + "-sealed".len() =>
+            Token::new_contextual_key(0, end, ContextualKeyword::NonSealed),
+        _ => match TokenKind::from_keyword(word) {
+            Some(kind) => Token::new_key(0, end, kind),
+            None => match ContextualKeyword::from_ident(word) {
+                Some(key) => Token::new_contextual_key(0, end, key),
+                None => Token::new_ident(0, end),
+            },
+        },
+    }
+}
+
+/// Lexes an operator, other than the '.', '...' and '@' punctuators.
+fn scan_op(b: &[u8]) -> (usize, TokenKind) {
+    use lexer::TokenKind::*;
+    let at = |ix: usize| if ix < b.len() { b[ix] } else { 0 };
+    // The longest operator matching wins
+    let with_eq = |op, op_eq| if at(1) == b'=' { (2, op_eq) } else { (1, op) };
+    match b[0] {
+        b'~' => (1, Tilde),
+        b'?' => (1, Question),
+        b':' if at(1) == b':' => (2, ColonColon),
+        b':' => (1, Colon),
+        b'=' => with_eq(Eq, EqEq),
+        b'!' => with_eq(Bang, BangEq),
+        b'*' => with_eq(Star, StarEq),
+        b'/' => with_eq(Slash, SlashEq),
+        b'%' => with_eq(Percent, PercentEq),
+        b'^' => with_eq(Caret, CaretEq),
+        b'+' if at(1) == b'+' => (2, PlusPlus),
+        b'+' => with_eq(Plus, PlusEq),
+        b'-' if at(1) == b'-' => (2, MinusMinus),
+        b'-' if at(1) == b'>' => (2, Arrow),
+        b'-' => with_eq(Minus, MinusEq),
+        b'&' if at(1) == b'&' => (2, AmpAmp),
+        b'&' => with_eq(Amp, AmpEq),
+        b'|' if at(1) == b'|' => (2, PipePipe),
+        b'|' => with_eq(Pipe, PipeEq),
+        b'<' => match (at(1), at(2)) {
+            (b'<', b'=') => (3, LtLtEq),
+            (b'<', _) => (2, LtLt),
+            _ => with_eq(Lt, LtEq),
+        },
+        b'>' => match (at(1), at(2), at(3)) {
+            (b'>', b'>', b'=') => (4, GtGtGtEq),
+            (b'>', b'>', _) => (3, GtGtGt),
+            (b'>', b'=', _) => (3, GtGtEq),
+            (b'>', _, _) => (2, GtGt),
+            _ => with_eq(Gt, GtEq),
+        },
+        c => unreachable!("'{}' isn't the start of an operator", c as char),
+    }
+}
+
+/// Lexes a /* style comment. "/**/" is empty, not the start of a doc comment.
+fn scan_block_comment(s: &str) -> Result<Token, LexErr> {
+    let body = &s[2..];
+    let kind = if body.starts_with('*') && !body.starts_with("*/") {
+        CommentKind::Doc
+    } else {
+        CommentKind::Block
+    };
+    match body.find("*/") {
+        Some(ix) => Ok(Token::new_comment(0, ix + 4, kind)),
+        None => Err(LexErr::Span("Unexpected EOF in block comment".to_owned(),
+                                 Point(0), Point(s.len()))),
+    }
+}
+
+/// Returns the end of a char or string literal. Literals can't span lines, so
+/// an unterminated literal ends at the end of its line.
+fn scan_quoted(b: &[u8], quote: u8, kind: &str) -> Result<usize, LexErr> {
+    // Keep consuming until we hit another unescaped quote
+    let mut ix = 1;
+    while ix < b.len() {
+        match b[ix] {
+            b'\n' | b'\r' => return Err(LexErr::Span(format!("Line terminator in {} literal", kind),
+                                                     Point(0), Point(ix))),
+            // Skip whatever's escaped, unless it's a line terminator
+            b'\\' if ix + 1 < b.len() && b[ix + 1] != b'\n' && b[ix + 1] != b'\r' => ix += 2,
+            c if c == quote => return Ok(ix + 1),
+            _ => ix += 1,
+        }
+    }
+    Err(LexErr::Span(format!("Unexpected EOF in {} literal", kind), Point(0), Point(b.len())))
+}
+
+/// Lexes a text block, i.e. a """ delimited multi-line string (JLS 3.10.6).
+fn scan_text_block(b: &[u8]) -> Result<Token, LexErr> {
+    // The opening delimiter must be followed by a line terminator. If it isn't,
+    // still consume the whole block so lexing can carry on after it.
+    let mut ix = 3;
+    while ix < b.len() && matches!(b[ix], b' ' | b'\t' | b'\x0c') { ix += 1; }
+    let bad_opening = ix < b.len() && b[ix] != b'\n' && b[ix] != b'\r';
+    // Keep consuming until we hit an unescaped """
+    while ix < b.len() {
+        match b[ix] {
+            b'\\' => ix += 2,
+            b'"' if b[ix..].starts_with(b"\"\"\"") => {
+                return if bad_opening {
+                    Err(LexErr::Span(
+                        "Text block opening delimiter must be followed by a line terminator"
+                            .to_owned(), Point(0), Point(ix + 3)))
+                } else {
+                    Ok(Token::new_text_block(0, ix + 3))
+                };
+            }
+            _ => ix += 1,
+        }
+    }
+    Err(LexErr::Span("Unexpected EOF in text block".to_owned(), Point(0), Point(b.len())))
+}
+
+fn is_dec_digit(b: u8) -> bool { b.is_ascii_digit() }
+fn is_oct_digit(b: u8) -> bool { (b'0'..=b'7').contains(&b) }
+fn is_bin_digit(b: u8) -> bool { b == b'0' || b == b'1' }
+fn is_hex_digit(b: u8) -> bool { b.is_ascii_hexdigit() }
+
+/// Scans a run of digits starting at `ix`, allowing underscores between (but
+/// not before or after) the digits. Returns the index just past the run, which
+/// is `ix` if no digits were found.
+fn scan_digits(b: &[u8], ix: usize, is_digit: fn(u8) -> bool) -> Result<usize, &'static str> {
+    let mut end = ix;
+    while end < b.len() && (is_digit(b[end]) || b[end] == b'_') { end += 1; }
+    if end > ix && (b[ix] == b'_' || b[end - 1] == b'_') {
+        Err("Underscores must be between digits in numeric literal")
+    } else {
+        Ok(end)
+    }
+}
+
+/// Scans an exponent ('e' or 'p' has already been checked by the caller) with
+/// an optional sign.
+fn scan_exponent(b: &[u8], ix: usize) -> Result<usize, &'static str> {
+    let mut ix = ix + 1;
+    if ix < b.len() && (b[ix] == b'+' || b[ix] == b'-') { ix += 1; }
+    match scan_digits(b, ix, is_dec_digit)? {
+        end if end == ix => Err("Malformed exponent in floating point literal"),
+        end => Ok(end),
+    }
+}
+
+/// Scans a numeric literal from the start of `b`, returning the length of the
+/// literal and whether it's a floating point literal. Follows JLS 3.10.1 and
+/// 3.10.2, i.e. hex, octal, binary and decimal integers with an optional 'L'
+/// suffix, and decimal / hex floats with optional exponents and 'f' / 'd'
+/// suffixes.
+fn scan_num_lit_len(b: &[u8]) -> Result<(usize, bool), &'static str> {
+    let at = |ix: usize| if ix < b.len() { b[ix] } else { 0 };
+    let radix_prefix = if at(0) == b'0' { at(1) | 0x20 } else { 0 };
+    if radix_prefix == b'x' {
+        let int_end = scan_digits(b, 2, is_hex_digit)?;
+        let mut end = int_end;
+        let mut has_digits = int_end > 2;
+        let mut is_float = false;
+        if at(end) == b'.' {
+            is_float = true;
+            let frac_start = end + 1;
+            end = scan_digits(b, frac_start, is_hex_digit)?;
+            has_digits = has_digits || end > frac_start;
+        }
+        if !has_digits {
+            return Err("Expected hex digits in numeric literal");
+        }
+        if at(end) | 0x20 == b'p' {
+            is_float = true;
+            end = scan_exponent(b, end)?;
+        } else if is_float {
+            return Err("Hex floating point literal requires a binary exponent");
+        }
+        match at(end) | 0x20 {
+            b'f' | b'd' if is_float => Ok((end + 1, true)),
+            b'l' if !is_float => Ok((end + 1, false)),
+            _ => Ok((end, is_float)),
+        }
+    } else if radix_prefix == b'b' {
+        let end = scan_digits(b, 2, is_bin_digit)?;
+        if end == 2 {
+            return Err("Expected binary digits in numeric literal");
+        }
+        match at(end) | 0x20 {
+            b'l' => Ok((end + 1, false)),
+            _ => Ok((end, false)),
+        }
+    } else {
+        let int_end = scan_digits(b, 0, is_dec_digit)?;
+        let mut end = int_end;
+        let mut is_float = false;
+        if at(end) == b'.' {
+            is_float = true;
+            end = scan_digits(b, end + 1, is_dec_digit)?;
+            if end == int_end + 1 && int_end == 0 {
+                return Err("Expected digits in floating point literal");
+            }
+        }
+        if at(end) | 0x20 == b'e' {
+            is_float = true;
+            end = scan_exponent(b, end)?;
+        }
+        match at(end) | 0x20 {
+            b'f' | b'd' => return Ok((end + 1, true)),
+            b'l' if is_float => return Err("Floating point literal cannot have an 'L' suffix"),
+            b'l' => end += 1,
+            _ => (),
+        }
+        // A leading 0 on an integer means octal
+        if !is_float && at(0) == b'0' && !b[1..int_end].iter().all(|&c| is_oct_digit(c) || c == b'_') {
+            return Err("Invalid digit in octal literal");
+        }
+        Ok((end, is_float))
+    }
+}
+
+/// Lexes a numeric literal. Signs are never part of the literal - '-1' is the
+/// unary minus operator applied to '1'.
+fn scan_num_lit(b: &[u8]) -> Result<Token, LexErr> {
+    let res = scan_num_lit_len(b);
+    // Numeric literals can't run straight into an identifier (e.g. '123abc'),
+    // so swallow the rest of the word and report the whole thing. If the
+    // literal itself is malformed, swallow everything that looks numeric too.
+    let lit_len = match res { Ok((len, _)) => len, Err(_) => 1 };
+    let mut len = lit_len;
+    while len < b.len() {
+        let c = b[len];
+        let is_word = c.is_ascii_alphanumeric() || c == b'_' || c == b'$';
+        let is_numeric = c == b'.' || ((c == b'+' || c == b'-')
+                                       && (b[len - 1] | 0x20 == b'e' || b[len - 1] | 0x20 == b'p'));
+        if is_word || (res.is_err() && is_numeric) {
+            len += 1;
+        } else { break }
+    }
+    match res {
+        Err(msg) => Err(LexErr::Span(msg.to_owned(), Point(0), Point(len))),
+        Ok(_) if len > lit_len => Err(LexErr::Span("Invalid character in numeric literal".to_owned(),
+                                                   Point(0), Point(len))),
+        Ok((_, true)) => Ok(Token::new_float_lit(0, len)),
+        Ok((_, false)) => Ok(Token::new_int_lit(0, len)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_match_char_classes() {
+        for b in 0..0x80u8 {
+            let c = b as char;
+            assert_eq!(START[b as usize] == Start::Whitespace, " \t\x0c\n\r".contains(c), "{:?}", c);
+            assert_eq!(START[b as usize] == Start::Ident, is_java_identifier_start(c), "{:?}", c);
+            assert_eq!(IDENT_PART[b as usize], is_java_identifier_part(c), "{:?}", c);
+        }
+        assert!((0x80..=0xff).all(|b| START[b] == Start::NonAscii && !IDENT_PART[b]));
+    }
+
+    #[test]
+    fn test_lex_token() {
+        let lex = |s| lex_token(s, JavaVersion::default());
+        let tok = lex("synchronized(x)").unwrap();
+        assert_eq!((tok.end.0, tok.kind), (12, TokenKind::Synchronized));
+        assert_eq!(lex("synchronizedx").unwrap().kind, TokenKind::Ident);
+        // Keywords which start with another keyword
+        let kinds: Vec<_> = ["double", "finally", "interface", "throws"].iter()
+            .map(|s| lex(s).unwrap().kind).collect();
+        assert_eq!(kinds, vec![TokenKind::Double, TokenKind::Finally, TokenKind::Interface,
+                               TokenKind::Throws]);
+        assert_eq!(lex("int\u{e9} = 1").unwrap().end.0, "int\u{e9}".len());
+        assert_eq!(lex(">>>=>").unwrap().kind, TokenKind::GtGtGtEq);
+        let tok = lex("non-sealed class").unwrap();
+        assert_eq!((tok.end.0, tok.contextual), (3, Some(ContextualKeyword::NonSealed)));
+        assert_eq!(lex("non-sealedx").unwrap().contextual, None);
+        assert_eq!(lex_token("_", JavaVersion(8)).unwrap().kind, TokenKind::Ident);
+        match lex("#") {
+            Err(LexErr::Span(_, start, end)) => assert_eq!((start.0, end.0), (0, 1)),
+            res => panic!("Expected error, got {:?}", res),
+        }
+        assert_eq!(scan_whitespace(" \t\x0c\r\n x"), " \t\x0c\r\n ".len());
+        // Vertical tabs and Unicode whitespace aren't Java whitespace
+        assert_eq!(scan_whitespace(" \x0b"), 1);
+        assert_eq!(scan_whitespace(" \u{a0}"), 1);
+        for s in &["\x0b", "\u{a0}", "\u{2003}"] {
+            match lex(s) {
+                Err(LexErr::Span(_, start, end)) => assert_eq!((start.0, end.0), (0, s.len())),
+                res => panic!("Expected error, got {:?}", res),
+            }
+        }
+    }
+}
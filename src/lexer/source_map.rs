@@ -0,0 +1,172 @@
+//! Mapping between byte offsets and line / column positions in a source file.
+
+use super::Point;
+
+/// A line and column in a source file. Everything is 0-based. Columns are
+/// given both in UTF-8 bytes and UTF-16 code units, since editors tend to
+/// count in the latter.
+#[derive(Ord, Eq, PartialEq, PartialOrd, Debug, Clone, Copy, Hash)]
+pub struct LineCol {
+    pub line: usize,
+    pub col_utf8: usize,
+    pub col_utf16: usize,
+}
+
+/// A non-ASCII char, whose UTF-8 and UTF-16 lengths differ.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+struct WideChar {
+    /// Byte offset of the char
+    offset: usize,
+    len_utf8: usize,
+    len_utf16: usize,
+    /// Total of (UTF-8 len - UTF-16 len) for all wide chars before this one
+    diff_before: usize,
+}
+
+/// An index of the lines in a source file. This is built once per file, after
+/// which lookups in either direction are O(log n).
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct SourceMap {
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    wide_chars: Vec<WideChar>,
+    /// Total of (UTF-8 len - UTF-16 len) for all wide chars
+    diff_total: usize,
+    len: usize,
+}
+
+impl SourceMap {
+    /// Index the given source. Lines are terminated by '\n', '\r' or "\r\n".
+    pub fn new(src: &str) -> SourceMap {
+        let mut line_starts = vec![0];
+        let mut wide_chars = Vec::new();
+        let mut diff_total = 0;
+        let mut chars = src.char_indices().peekable();
+        while let Some((ix, c)) = chars.next() {
+            match c {
+                '\n' => line_starts.push(ix + 1),
+                '\r' => match chars.peek() {
+                    Some(&(_, '\n')) => (),
+                    _ => line_starts.push(ix + 1),
+                },
+                c if !c.is_ascii() => {
+                    wide_chars.push(WideChar { offset: ix, len_utf8: c.len_utf8(),
+                                               len_utf16: c.len_utf16(),
+                                               diff_before: diff_total });
+                    diff_total += c.len_utf8() - c.len_utf16();
+                }
+                _ => (),
+            }
+        }
+        SourceMap { line_starts, wide_chars, diff_total, len: src.len() }
+    }
+
+    /// The number of lines in the source.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Total of (UTF-8 len - UTF-16 len) for all chars before the given offset.
+    fn diff_before(&self, offset: usize) -> usize {
+        match self.wide_chars.binary_search_by(|w| w.offset.cmp(&offset)) {
+            Ok(ix) => self.wide_chars[ix].diff_before,
+            Err(ix) if ix == self.wide_chars.len() => self.diff_total,
+            Err(ix) => self.wide_chars[ix].diff_before,
+        }
+    }
+
+    /// Returns the line and column of a byte offset. Offsets past the end of
+    /// the source are clamped to the end.
+    pub fn line_col(&self, p: Point) -> LineCol {
+        let offset = p.0.min(self.len);
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let line_start = self.line_starts[line];
+        let col_utf8 = offset - line_start;
+        let col_utf16 = col_utf8 - (self.diff_before(offset) - self.diff_before(line_start));
+        LineCol { line, col_utf8, col_utf16 }
+    }
+
+    /// Returns the byte offset of a line and UTF-8 column, or None if it's out
+    /// of range.
+    pub fn offset_utf8(&self, line: usize, col: usize) -> Option<Point> {
+        let line_start = *self.line_starts.get(line)?;
+        let line_end = self.line_starts.get(line + 1).cloned().unwrap_or(self.len);
+        if line_start + col <= line_end { Some(Point(line_start + col)) } else { None }
+    }
+
+    /// Returns the byte offset of a line and UTF-16 column, or None if it's out
+    /// of range. Columns inside a surrogate pair map to the start of the char.
+    pub fn offset_utf16(&self, line: usize, col: usize) -> Option<Point> {
+        let line_start = *self.line_starts.get(line)?;
+        let line_end = self.line_starts.get(line + 1).cloned().unwrap_or(self.len);
+        let base_diff = self.diff_before(line_start);
+        let utf16_col = |offset: usize| offset - line_start - (self.diff_before(offset) - base_diff);
+        // Find the last wide char in the line starting at or before the column
+        let first = match self.wide_chars.binary_search_by(|w| w.offset.cmp(&line_start)) {
+            Ok(ix) | Err(ix) => ix,
+        };
+        let last = match self.wide_chars.binary_search_by(|w| w.offset.cmp(&line_end)) {
+            Ok(ix) | Err(ix) => ix,
+        };
+        let in_line = &self.wide_chars[first..last];
+        let offset = match in_line.binary_search_by(|w| utf16_col(w.offset).cmp(&col)) {
+            Ok(ix) => in_line[ix].offset,
+            Err(0) => line_start + col,
+            Err(ix) => {
+                let w = in_line[ix - 1];
+                let w_col = utf16_col(w.offset);
+                if col < w_col + w.len_utf16 {
+                    w.offset
+                } else {
+                    w.offset + w.len_utf8 + (col - w_col - w.len_utf16)
+                }
+            }
+        };
+        if offset <= line_end { Some(Point(offset)) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col() {
+        let src = "ab\n\u{e9}c\r\n\u{1F600}d\re";
+        let map = SourceMap::new(src);
+        assert_eq!(map.line_count(), 4);
+        let lc = |offset| {
+            let lc = map.line_col(Point(offset));
+            (lc.line, lc.col_utf8, lc.col_utf16)
+        };
+        assert_eq!(lc(0), (0, 0, 0));
+        assert_eq!(lc(2), (0, 2, 2));
+        assert_eq!(lc(3), (1, 0, 0));
+        // After the 2 byte char
+        assert_eq!(lc(5), (1, 2, 1));
+        assert_eq!(lc(6), (1, 3, 2));
+        assert_eq!(lc(8), (2, 0, 0));
+        // After the 4 byte char, which is 2 UTF-16 units
+        assert_eq!(lc(12), (2, 4, 2));
+        assert_eq!(lc(14), (3, 0, 0));
+        assert_eq!(lc(100), (3, 1, 1));
+    }
+
+    #[test]
+    fn test_offset() {
+        let src = "ab\n\u{e9}c\r\n\u{1F600}d\re";
+        let map = SourceMap::new(src);
+        for offset in [0, 2, 3, 5, 6, 8, 12, 13, 14, 15].iter() {
+            let lc = map.line_col(Point(*offset));
+            assert_eq!(map.offset_utf8(lc.line, lc.col_utf8), Some(Point(*offset)));
+            assert_eq!(map.offset_utf16(lc.line, lc.col_utf16), Some(Point(*offset)));
+        }
+        // Inside the surrogate pair
+        assert_eq!(map.offset_utf16(2, 1), Some(Point(8)));
+        assert_eq!(map.offset_utf16(0, 4), None);
+        assert_eq!(map.offset_utf8(4, 0), None);
+    }
+}
@@ -0,0 +1,359 @@
+//! A streaming lexer, which lexes one token at a time rather than the whole
+//! file up front.
+
+use std::cmp;
+use super::{JavaVersion, LexErr, Point, SourceMap, Token, TokenType, Translated};
+use super::scan::{lex_token, scan_whitespace};
+use super::unicode::escape_start;
+
+/// How much of the source has its unicode escapes translated at a time. The
+/// window grows if a token doesn't fit in it.
+const WINDOW_LEN: usize = 64 * 1024;
+
+/// How far past the end of a token lexing it may look. A token is only lexed
+/// from the window if the window goes at least this far past it, so the end of
+/// the window can't have cut it short.
+const LOOKAHEAD: usize = 16;
+
+/// Lexes tokens from some source on demand. Only the current position and a
+/// window of source with its unicode escapes translated are kept between
+/// tokens, so memory use doesn't grow with the number of tokens lexed - e.g.
+/// lexing just the package and imports of a huge file is cheap.
+///
+/// By default only significant tokens are produced, with the whitespace and
+/// comments around them attached as trivia (see `Token`).
+///
+/// Errors are spanned and point into the original source. After an error the
+/// lexer carries on from the end of the offending source, so iterating to the
+/// end finds every error.
+pub struct Lexer<'a> {
+    src: &'a str,
+    file: &'a str,
+    version: JavaVersion,
+    /// The source from `win_start` to `win_end`, with its escapes translated
+    window: Translated<'a>,
+    win_start: usize,
+    win_end: usize,
+    /// Whether to produce trivia as tokens of their own
+    raw: bool,
+    /// Offset of the next token
+    pos: usize,
+    /// Offset of the next token's leading trivia
+    full_start: usize,
+    /// A token which contains a bad unicode escape, to return after the
+    /// escape's error
+    pending: Option<Token>,
+    peeked: Option<Option<Result<Token, LexErr>>>,
+    /// Only built once there's an error to report
+    source_map: Option<SourceMap>,
+}
+
+impl<'a> Lexer<'a> {
+    /// Create a lexer for the given source, as the latest version of Java.
+    pub fn new(src: &'a str, file: &'a str) -> Lexer<'a> {
+        Lexer::with_version(src, file, JavaVersion::default())
+    }
+
+    /// Create a lexer for the given source, as the given version of Java.
+    pub fn with_version(src: &'a str, file: &'a str, version: JavaVersion) -> Lexer<'a> {
+        Lexer {
+            src,
+            file,
+            version,
+            window: Translated::new_tolerant_part(src, 0, 0),
+            win_start: 0,
+            win_end: 0,
+            raw: false,
+            pos: 0,
+            full_start: 0,
+            pending: None,
+            peeked: None,
+            source_map: None,
+        }
+    }
+
+    /// Produce whitespace and comments as tokens of their own, rather than as
+    /// trivia attached to the significant tokens.
+    pub fn raw(mut self) -> Lexer<'a> {
+        self.raw = true;
+        self
+    }
+
+    /// Returns the next token without consuming it.
+    pub fn peek(&mut self) -> Option<&Result<Token, LexErr>> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex_next());
+        }
+        self.peeked.as_ref().unwrap().as_ref()
+    }
+
+    /// Carry on lexing from the given offset into the original source, which
+    /// should be the start of a token or its leading trivia. An offset inside a
+    /// char or a unicode escape is moved back to its start, and one past the end
+    /// of the source to the end.
+    pub fn restart(&mut self, offset: Point) {
+        let mut offset = offset.0.min(self.src.len());
+        while !self.src.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        self.pos = escape_start(self.src, offset);
+        self.full_start = self.pos;
+        self.pending = None;
+        self.peeked = None;
+    }
+
+    /// Adds the file, line and column to an error.
+    fn with_point(&mut self, e: LexErr) -> LexErr {
+        let offset = match e {
+            LexErr::Span(_, start, _) => start,
+            _ => Point(self.pos),
+        };
+        let src = self.src;
+        let map = self.source_map.get_or_insert_with(|| SourceMap::new(src));
+        e.into_point(self.file.to_string(), map.line_col(offset))
+    }
+
+    /// Translates the source from `pos` on. If the window already starts at
+    /// `pos`, it's doubled in size instead, for tokens which didn't fit in it.
+    fn move_window(&mut self) {
+        // Growing in place has to get past the old end, or it would never stop
+        let (mut len, min_end) = if self.win_start == self.pos {
+            (cmp::max(2 * (self.win_end - self.win_start), WINDOW_LEN), self.win_end)
+        } else {
+            (WINDOW_LEN, self.pos)
+        };
+        let end = loop {
+            let mut end = cmp::min(self.pos + len, self.src.len());
+            while !self.src.is_char_boundary(end) {
+                end -= 1;
+            }
+            // Don't cut an escape in two
+            let end = escape_start(self.src, end);
+            if end > min_end || end == self.src.len() { break end }
+            len *= 2;
+        };
+        self.window = Translated::new_tolerant_part(self.src, self.pos, end);
+        self.win_start = self.pos;
+        self.win_end = end;
+    }
+
+    /// Makes sure the window has `pos` in it, and goes at least `LOOKAHEAD` past
+    /// it unless it reaches the end of the source.
+    fn fill_window(&mut self) {
+        if self.pos < self.win_start || self.pos > self.win_end
+            || (self.win_end < self.src.len() && self.win_end - self.pos < LOOKAHEAD) {
+            self.move_window();
+        }
+    }
+
+    /// Lexes the next token from the window, significant or not. Returns the
+    /// offset into the window's text just past it.
+    fn lex_window(&self) -> (usize, Result<Token, LexErr>) {
+        let start = self.window.translated_offset(self.pos);
+        let text = &self.window.text[start..];
+
+        // Bad unicode escapes are left untranslated, so report them here
+        if let Some((end, e)) = self.window.bad_escape_in(self.pos, self.pos + 1) {
+            return (self.window.translated_offset(end), Err(e.clone()));
+        }
+
+        let len = scan_whitespace(text);
+        if len > 0 {
+            let tok = Token::new_whitespace(start, start + len);
+            return (start + len, Ok(self.window.orig_token(tok)));
+        }
+
+        // The tokens and errors from lex_token are relative to 'start'
+        match lex_token(text, self.version) {
+            Ok(tok) => {
+                let (tok_start, tok_end) = (Point(tok.start.0 + start), Point(tok.end.0 + start));
+                let tok = Token { start: tok_start, end: tok_end, full_start: tok_start,
+                                  full_end: tok_end, .. tok };
+                (tok_end.0, Ok(self.window.orig_token(tok)))
+            }
+            Err(LexErr::Span(msg, _, end)) => {
+                // Resume after the offending source
+                let end = start + end.0;
+                (end, Err(self.window.orig_err(LexErr::Span(msg, Point(start), Point(end)))))
+            }
+            Err(e) => unreachable!("lex_token returned {:?}", e),
+        }
+    }
+
+    /// Lexes the next token, significant or not. Tokens and errors point into
+    /// the original source.
+    fn lex_any(&mut self) -> Option<Result<Token, LexErr>> {
+        if self.pos == self.src.len() {
+            return None;
+        }
+        self.fill_window();
+        loop {
+            let (end, res) = self.lex_window();
+            if self.win_end == self.src.len() || end + LOOKAHEAD <= self.window.text.len() {
+                self.pos = self.window.orig_offset(end);
+                return Some(res);
+            }
+            // The token may carry on past the window
+            self.move_window();
+        }
+    }
+
+    /// Returns true if whitespace or a comment is next, without lexing it.
+    fn is_trivia_next(&mut self) -> bool {
+        if self.pos == self.src.len() {
+            return false;
+        }
+        self.fill_window();
+        let text = &self.window.text[self.window.translated_offset(self.pos)..];
+        text.starts_with("//") || text.starts_with("/*") || scan_whitespace(text) > 0
+    }
+
+    /// Skips trivia, returning the offset just past it. If `line` is set, stops
+    /// after the first line terminator. Anything else, including errors and
+    /// comments with bad escapes, is left for the next token.
+    fn skip_trivia(&mut self, line: bool) -> usize {
+        loop {
+            // Don't lex the next token just to throw it away
+            if !self.is_trivia_next() {
+                return self.pos;
+            }
+            let before = self.pos;
+            match self.lex_any() {
+                Some(Ok(tok)) if tok.token_type == TokenType::Whitespace && line => {
+                    // The line terminator may be escaped
+                    let start = self.window.translated_offset(tok.start.0);
+                    let ws = &self.window.text[start..self.window.translated_offset(tok.end.0)];
+                    if let Some(ix) = ws.find(&['\n', '\r'][..]) {
+                        let len = if ws[ix..].starts_with("\r\n") { 2 } else { 1 };
+                        self.pos = self.window.orig_offset(start + ix + len);
+                        return self.pos;
+                    }
+                }
+                Some(Ok(tok)) if tok.is_trivia()
+                    && self.window.bad_escape_in(tok.start.0, tok.end.0).is_none() => (),
+                Some(_) => {
+                    self.pos = before;
+                    return before;
+                }
+                None => return self.pos,
+            }
+        }
+    }
+
+    fn lex_next(&mut self) -> Option<Result<Token, LexErr>> {
+        if let Some(tok) = self.pending.take() {
+            return Some(Ok(tok));
+        }
+
+        let tok = loop {
+            match self.lex_any()? {
+                Ok(tok) if tok.is_trivia() && !self.raw => (),
+                Ok(tok) => break tok,
+                Err(e) => {
+                    // The error's source isn't trivia of the next token
+                    self.full_start = self.pos;
+                    return Some(Err(self.with_point(e)));
+                }
+            }
+        };
+        // A bad escape inside a literal or comment is reported before the
+        // token itself. Look for one before the window moves past the token.
+        let bad_escape = self.window.bad_escape_in(tok.start.0, tok.end.0).map(|(_, e)| e.clone());
+
+        let tok = if self.raw {
+            tok
+        } else {
+            // Trivia left at the end of the file is given to the last token
+            let trailing_end = self.skip_trivia(true);
+            let next_start = self.skip_trivia(false);
+            let full_end = if next_start == self.src.len() {
+                next_start
+            } else {
+                trailing_end
+            };
+            let full_start = self.full_start;
+            self.full_start = full_end;
+            Token { full_start: Point(full_start), full_end: Point(full_end), .. tok }
+        };
+
+        if let Some(e) = bad_escape {
+            self.pending = Some(tok);
+            return Some(Err(self.with_point(e)));
+        }
+        Some(Ok(tok))
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token, LexErr>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(item) => item,
+            None => self.lex_next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lexer() {
+        let src = "package a.b;\nimport c.D;\nclass \\u0045 { String s = \"\\u00g1\"; }";
+        let mut lexer = Lexer::new(src, "E.java");
+        assert_eq!(lexer.peek().unwrap().as_ref().unwrap().val(src), "package");
+        assert_eq!(lexer.next().unwrap().unwrap().val(src), "package");
+        let vals: Vec<_> = lexer.by_ref().take(5).map(|t| t.unwrap().val(src)).collect();
+        assert_eq!(vals, vec!["a", ".", "b", ";", "import"]);
+
+        // Restarting at the class keyword
+        lexer.restart(Point(src.find("class").unwrap()));
+        let tok = lexer.next().unwrap().unwrap();
+        assert_eq!(tok.token_type, TokenType::Key);
+        assert_eq!(lexer.next().unwrap().unwrap().val(src), "\\u0045");
+
+        // The bad escape in the string is reported, then the string itself
+        let res: Vec<_> = lexer.skip(4).collect();
+        match res[0] {
+            Err(LexErr::SpanPoint(_, _, lc, start, end)) => {
+                assert_eq!(&src[start.0..end.0], "\\u00g1");
+                assert_eq!((lc.line, lc.col_utf8), (2, 27));
+            }
+            ref res => panic!("Expected error, got {:?}", res),
+        }
+        assert_eq!(res[1].as_ref().unwrap().val(src), "\"\\u00g1\"");
+        assert_eq!(res.len(), 4);
+    }
+
+    #[test]
+    fn test_restart_inside_char() {
+        let src = "a \u{e9}t\u{e9}";
+        let mut lexer = Lexer::new(src, "");
+        lexer.restart(Point(3));
+        assert_eq!(lexer.next().unwrap().unwrap().val(src), "\u{e9}t\u{e9}");
+        lexer.restart(Point(src.len() - 1));
+        assert_eq!(lexer.next().unwrap().unwrap().val(src), "\u{e9}");
+        lexer.restart(Point(src.len() + 1));
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_lexer_window() {
+        // A comment longer than the window, with escapes all through it
+        let comment = format!("/*{}*/", "\\u0041 ".repeat(WINDOW_LEN / 4));
+        let src = format!("a {}\\u0062 c", comment);
+        let vals: Vec<_> = Lexer::new(&src, "").map(|t| t.unwrap().val(&src)).collect();
+        assert_eq!(vals, vec!["a", "\\u0062", "c"]);
+        let vals: Vec<_> = Lexer::new(&src, "").raw().map(|t| t.unwrap().val(&src)).collect();
+        assert_eq!(vals[2], comment);
+
+        // An escape can be longer than the window too
+        let src = format!("9\\{}", "u".repeat(2 * WINDOW_LEN));
+        let mut lexer = Lexer::new(&src, "");
+        assert_eq!(lexer.next().unwrap().unwrap().val(&src), "9");
+        assert!(lexer.next().unwrap().is_err());
+        assert!(lexer.next().is_none());
+    }
+}
@@ -5,62 +5,324 @@ pub struct Point(pub usize);
 #[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
 pub enum TokenType {
     Ident, Punc, Key, Op,
-    IntLit, FloatLit, StringLit, CharLit, BoolLit, NullLit,
-    Comment
+    IntLit, FloatLit, StringLit, TextBlock, CharLit, BoolLit, NullLit,
+    Comment(CommentKind),
+    /// A run of whitespace. Like comments, this is trivia, and is only produced
+    /// by `lex_raw`.
+    Whitespace,
+    /// Source which couldn't be lexed. Only produced when lexing tolerantly.
+    Error,
 }
 
+/// The kind of a comment.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum CommentKind {
+    /// A // style comment, up to the end of the line
+    Line,
+    /// A /* style comment
+    Block,
+    /// A /** style (Javadoc) comment. "/**/" is an empty block comment, not a
+    /// doc comment.
+    Doc,
+}
+
+/// The specific kind of a token - one per keyword, operator and punctuator, so
+/// the parser can match on kinds rather than comparing strings. Everything else
+/// is just an identifier, literal, trivia or error.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum TokenKind {
+    Ident, Literal, Trivia, Error,
+    // Keywords
+    Abstract, Assert, Boolean, Break, Byte, Case, Catch, Char, Class, Const, Continue,
+    Default, Do, Double, Else, Enum, Extends, Final, Finally, Float, For, Goto, If,
+    Implements, Import, Instanceof, Int, Interface, Long, Native, New, Package, Private,
+    Protected, Public, Return, Short, Static, Strictfp, Super, Switch, Synchronized, This,
+    Throw, Throws, Transient, Try, Void, Volatile, While, Underscore,
+    // Punctuators
+    Dot, Ellipsis, Comma, LParen, RParen, LBracket, RBracket, LBrace, RBrace, Semi, At,
+    // Operators
+    Tilde, Question, Colon, ColonColon, Eq, EqEq, Bang, BangEq, Plus, PlusEq, PlusPlus, Minus, MinusEq,
+    MinusMinus, Arrow, Star, StarEq, Slash, SlashEq, Percent, PercentEq, Caret, CaretEq,
+    Amp, AmpAmp, AmpEq, Pipe, PipePipe, PipeEq, Lt, LtEq, LtLt, LtLtEq, Gt, GtEq, GtGt,
+    GtGtEq, GtGtGt, GtGtGtEq,
+}
+
+const KEYWORDS: &[(&str, TokenKind)] = &[
+    ("abstract", TokenKind::Abstract), ("assert", TokenKind::Assert),
+    ("boolean", TokenKind::Boolean), ("break", TokenKind::Break),
+    ("byte", TokenKind::Byte), ("case", TokenKind::Case), ("catch", TokenKind::Catch),
+    ("char", TokenKind::Char), ("class", TokenKind::Class), ("const", TokenKind::Const),
+    ("continue", TokenKind::Continue), ("default", TokenKind::Default),
+    ("do", TokenKind::Do), ("double", TokenKind::Double), ("else", TokenKind::Else),
+    ("enum", TokenKind::Enum), ("extends", TokenKind::Extends),
+    ("final", TokenKind::Final), ("finally", TokenKind::Finally),
+    ("float", TokenKind::Float), ("for", TokenKind::For), ("goto", TokenKind::Goto),
+    ("if", TokenKind::If), ("implements", TokenKind::Implements),
+    ("import", TokenKind::Import), ("instanceof", TokenKind::Instanceof),
+    ("int", TokenKind::Int), ("interface", TokenKind::Interface),
+    ("long", TokenKind::Long), ("native", TokenKind::Native), ("new", TokenKind::New),
+    ("package", TokenKind::Package), ("private", TokenKind::Private),
+    ("protected", TokenKind::Protected), ("public", TokenKind::Public),
+    ("return", TokenKind::Return), ("short", TokenKind::Short),
+    ("static", TokenKind::Static), ("strictfp", TokenKind::Strictfp),
+    ("super", TokenKind::Super), ("switch", TokenKind::Switch),
+    ("synchronized", TokenKind::Synchronized), ("this", TokenKind::This),
+    ("throw", TokenKind::Throw), ("throws", TokenKind::Throws),
+    ("transient", TokenKind::Transient), ("try", TokenKind::Try),
+    ("void", TokenKind::Void), ("volatile", TokenKind::Volatile),
+    ("while", TokenKind::While), ("_", TokenKind::Underscore),
+];
+
+const SYMBOLS: &[(&str, TokenKind)] = &[
+    (".", TokenKind::Dot), ("...", TokenKind::Ellipsis), (",", TokenKind::Comma),
+    ("(", TokenKind::LParen), (")", TokenKind::RParen), ("[", TokenKind::LBracket),
+    ("]", TokenKind::RBracket), ("{", TokenKind::LBrace), ("}", TokenKind::RBrace),
+    (";", TokenKind::Semi), ("@", TokenKind::At), ("~", TokenKind::Tilde),
+    ("?", TokenKind::Question), (":", TokenKind::Colon), ("::", TokenKind::ColonColon),
+    ("=", TokenKind::Eq),
+    ("==", TokenKind::EqEq), ("!", TokenKind::Bang), ("!=", TokenKind::BangEq),
+    ("+", TokenKind::Plus), ("+=", TokenKind::PlusEq), ("++", TokenKind::PlusPlus),
+    ("-", TokenKind::Minus), ("-=", TokenKind::MinusEq), ("--", TokenKind::MinusMinus),
+    ("->", TokenKind::Arrow), ("*", TokenKind::Star), ("*=", TokenKind::StarEq),
+    ("/", TokenKind::Slash), ("/=", TokenKind::SlashEq), ("%", TokenKind::Percent),
+    ("%=", TokenKind::PercentEq), ("^", TokenKind::Caret), ("^=", TokenKind::CaretEq),
+    ("&", TokenKind::Amp), ("&&", TokenKind::AmpAmp), ("&=", TokenKind::AmpEq),
+    ("|", TokenKind::Pipe), ("||", TokenKind::PipePipe), ("|=", TokenKind::PipeEq),
+    ("<", TokenKind::Lt), ("<=", TokenKind::LtEq), ("<<", TokenKind::LtLt),
+    ("<<=", TokenKind::LtLtEq), (">", TokenKind::Gt), (">=", TokenKind::GtEq),
+    (">>", TokenKind::GtGt), (">>=", TokenKind::GtGtEq), (">>>", TokenKind::GtGtGt),
+    (">>>=", TokenKind::GtGtGtEq),
+];
+
+/// The number of slots in `KEYWORD_TABLE`
+const KEYWORD_SLOTS: usize = 128;
+
+/// The length of the longest keyword, 'synchronized'
+const MAX_KEYWORD_LEN: usize = 12;
+
+/// Hashes a non-empty word into `KEYWORD_TABLE`, from its first two bytes, its
+/// last byte and its length. The multiplier was searched for so that no two
+/// keywords collide, making this a perfect hash - `build_keyword_table` checks
+/// that at compile time.
+const fn keyword_hash(b: &[u8]) -> usize {
+    let second = if b.len() > 1 { b[1] } else { b[0] };
+    let key = b[0] as u32 | (second as u32) << 8 | (b[b.len() - 1] as u32) << 16
+        | (b.len() as u32) << 24;
+    (key.wrapping_mul(0xd2e0_34ff) >> 25) as usize
+}
+
+const fn build_keyword_table() -> [Option<(&'static str, TokenKind)>; KEYWORD_SLOTS] {
+    let mut table = [None; KEYWORD_SLOTS];
+    let mut i = 0;
+    while i < KEYWORDS.len() {
+        let (word, kind) = KEYWORDS[i];
+        let slot = keyword_hash(word.as_bytes());
+        assert!(table[slot].is_none(), "Keywords collide in keyword_hash");
+        table[slot] = Some((word, kind));
+        i += 1;
+    }
+    table
+}
+
+/// Keywords by `keyword_hash`, so looking up a word is one hash and at most one
+/// comparison.
+static KEYWORD_TABLE: [Option<(&str, TokenKind)>; KEYWORD_SLOTS] = build_keyword_table();
+
+impl TokenKind {
+    /// Returns the kind of a keyword, or None if `s` isn't a keyword.
+    pub fn from_keyword(s: &str) -> Option<TokenKind> {
+        if s.is_empty() || s.len() > MAX_KEYWORD_LEN {
+            return None;
+        }
+        match KEYWORD_TABLE[keyword_hash(s.as_bytes())] {
+            Some((k, kind)) if k == s => Some(kind),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of an operator or punctuator, or None if `s` isn't one.
+    pub fn from_symbol(s: &str) -> Option<TokenKind> {
+        SYMBOLS.iter().find(|&&(k, _)| k == s).map(|&(_, kind)| kind)
+    }
+
+    /// The source text of a keyword, operator or punctuator. Other kinds are
+    /// described rather than spelled out.
+    pub fn text(self) -> &'static str {
+        match self {
+            TokenKind::Ident => "identifier",
+            TokenKind::Literal => "literal",
+            TokenKind::Trivia => "trivia",
+            TokenKind::Error => "error",
+            kind => KEYWORDS.iter().chain(SYMBOLS.iter())
+                .find(|&&(_, k)| k == kind).map(|&(text, _)| text).unwrap(),
+        }
+    }
+
+    /// For a compound token starting with '>', returns the kind of what's left
+    /// after splitting off the first '>', e.g. '>=' for '>>='.
+    pub fn split_gt(self) -> Option<TokenKind> {
+        match self {
+            TokenKind::GtEq => Some(TokenKind::Eq),
+            TokenKind::GtGt => Some(TokenKind::Gt),
+            TokenKind::GtGtEq => Some(TokenKind::GtEq),
+            TokenKind::GtGtGt => Some(TokenKind::GtGt),
+            TokenKind::GtGtGtEq => Some(TokenKind::GtGtEq),
+            _ => None,
+        }
+    }
+}
+
+/// Identifiers which are only keywords in certain positions, like 'var' or
+/// 'record'. These are lexed as identifiers tagged with their keyword, and the
+/// parser decides from the position whether they're acting as keywords.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum ContextualKeyword {
+    Var, Yield, Record, Sealed, NonSealed, Permits,
+    // Module declarations
+    Module, Open, Requires, Exports, Opens, Uses, Provides, To, With, Transitive,
+}
+
+impl ContextualKeyword {
+    /// Returns the contextual keyword an identifier may act as, if any.
+    pub fn from_ident(s: &str) -> Option<ContextualKeyword> {
+        use self::ContextualKeyword::*;
+        Some(match s {
+            "var" => Var,
+            "yield" => Yield,
+            "record" => Record,
+            "sealed" => Sealed,
+            "permits" => Permits,
+            "module" => Module,
+            "open" => Open,
+            "requires" => Requires,
+            "exports" => Exports,
+            "opens" => Opens,
+            "uses" => Uses,
+            "provides" => Provides,
+            "to" => To,
+            "with" => With,
+            "transitive" => Transitive,
+            _ => return None,
+        })
+    }
+}
+
+/// A token. Significant tokens also own the trivia (whitespace and comments)
+/// around them: `full_start..start` is the leading trivia, and `end..full_end`
+/// is the trailing trivia, which runs up to and including the end of the line.
+/// Concatenating the full source of every token reproduces the file.
 #[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
 pub struct Token {
     pub start: Point,
     pub end: Point,
+    pub full_start: Point,
+    pub full_end: Point,
     pub token_type: TokenType,
+    pub kind: TokenKind,
+    /// Set for identifiers which may act as a contextual keyword
+    pub contextual: Option<ContextualKeyword>,
 }
 
 impl<'a> Token {
+    fn new(start: usize, end: usize, token_type: TokenType) -> Token {
+        let kind = match token_type {
+            TokenType::Ident => TokenKind::Ident,
+            TokenType::Comment(_) | TokenType::Whitespace => TokenKind::Trivia,
+            TokenType::Error => TokenKind::Error,
+            _ => TokenKind::Literal,
+        };
+        Token { start: Point(start), end: Point(end), full_start: Point(start),
+                full_end: Point(end), token_type, kind, contextual: None }
+    }
     pub fn new_ident(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::Ident }
+        Token::new(start, end, TokenType::Ident)
     }
-    pub fn new_punc(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::Punc }
+    pub fn new_contextual_key(start: usize, end: usize, key: ContextualKeyword) -> Token {
+        Token { contextual: Some(key), .. Token::new(start, end, TokenType::Ident) }
     }
-    pub fn new_key(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::Key }
+    pub fn new_punc(start: usize, end: usize, kind: TokenKind) -> Token {
+        Token { kind, .. Token::new(start, end, TokenType::Punc) }
     }
-    pub fn new_op(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::Op }
+    pub fn new_key(start: usize, end: usize, kind: TokenKind) -> Token {
+        Token { kind, .. Token::new(start, end, TokenType::Key) }
+    }
+    pub fn new_op(start: usize, end: usize, kind: TokenKind) -> Token {
+        Token { kind, .. Token::new(start, end, TokenType::Op) }
     }
     pub fn new_float_lit(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::FloatLit }
+        Token::new(start, end, TokenType::FloatLit)
     }
     pub fn new_int_lit(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::IntLit }
+        Token::new(start, end, TokenType::IntLit)
     }
     pub fn new_null_lit(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::NullLit }
+        Token::new(start, end, TokenType::NullLit)
     }
     pub fn new_char_lit(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::CharLit }
+        Token::new(start, end, TokenType::CharLit)
     }
     pub fn new_string_lit(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::StringLit }
+        Token::new(start, end, TokenType::StringLit)
+    }
+    pub fn new_text_block(start: usize, end: usize) -> Token {
+        Token::new(start, end, TokenType::TextBlock)
     }
     pub fn new_bool_lit(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::BoolLit }
+        Token::new(start, end, TokenType::BoolLit)
     }
-    pub fn new_comment(start: usize, end: usize) -> Token {
-        Token { start: Point(start), end: Point(end), token_type: TokenType::Comment }
+    pub fn new_comment(start: usize, end: usize, kind: CommentKind) -> Token {
+        Token::new(start, end, TokenType::Comment(kind))
+    }
+
+    pub fn new_whitespace(start: usize, end: usize) -> Token {
+        Token::new(start, end, TokenType::Whitespace)
+    }
+
+    pub fn new_error(start: usize, end: usize) -> Token {
+        Token::new(start, end, TokenType::Error)
     }
 
     pub fn val(&self, src: &'a str) -> &'a str {
         unsafe {
-            src.slice_unchecked(self.start.0, self.end.0)
+            src.get_unchecked(self.start.0..self.end.0)
         }
     }
 
+    /// Returns true if this is an identifier which may be the given contextual
+    /// keyword.
+    pub fn is_contextual_key(&self, key: ContextualKeyword) -> bool {
+        self.contextual == Some(key)
+    }
+
+    /// The source of this token including its trivia.
+    pub fn full_val(&self, src: &'a str) -> &'a str {
+        &src[self.full_start.0..self.full_end.0]
+    }
+
+    /// The whitespace and comments before this token.
+    pub fn leading_trivia(&self, src: &'a str) -> &'a str {
+        &src[self.full_start.0..self.start.0]
+    }
+
+    /// The whitespace and comments after this token, up to the end of the line.
+    pub fn trailing_trivia(&self, src: &'a str) -> &'a str {
+        &src[self.end.0..self.full_end.0]
+    }
+
+    /// Returns true for whitespace and comments.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.token_type, TokenType::Whitespace | TokenType::Comment(_))
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.token_type, TokenType::Comment(_))
+    }
+
     pub fn is_literal(&self) -> bool {
         self.token_type == TokenType::IntLit ||
             self.token_type == TokenType::FloatLit ||
             self.token_type == TokenType::StringLit ||
+            self.token_type == TokenType::TextBlock ||
             self.token_type == TokenType::CharLit ||
             self.token_type == TokenType::BoolLit ||
             self.token_type == TokenType::NullLit
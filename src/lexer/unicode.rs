@@ -0,0 +1,340 @@
+//! Translation of unicode escapes (\uXXXX), as described in JLS 3.3. This
+//! happens before any other lexing, so escapes can appear anywhere in the
+//! source - in identifiers, literals, comments and so on.
+
+use std::borrow::Cow;
+use super::{LexErr, Point, Token};
+
+/// A translated unicode escape. `start..end` is the range of the translated
+/// char in the translated text, `orig_start..orig_end` is the range of the
+/// escape in the original source.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+struct Escape {
+    start: usize,
+    end: usize,
+    orig_start: usize,
+    orig_end: usize,
+}
+
+/// An escape which couldn't be translated, and so was left as it is. `start..end`
+/// is its range in the original source.
+#[derive(Eq, PartialEq, Debug, Clone)]
+struct BadEscape {
+    start: usize,
+    end: usize,
+    err: LexErr,
+}
+
+/// Source code with all unicode escapes translated. Offsets in the translated
+/// text can be mapped back into the original source with `orig_offset`.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Translated<'a> {
+    /// The translated source. This borrows the original source if there were no
+    /// escapes to translate.
+    pub text: Cow<'a, str>,
+    /// Where the translated part of the original source starts
+    base: usize,
+    escapes: Vec<Escape>,
+    bad_escapes: Vec<BadEscape>,
+}
+
+/// Returns the index just past the end of the unicode escape starting at `ix`,
+/// which must be an eligible '\'. Returns None if this isn't a unicode escape.
+/// The end is 4 chars after the 'u's, or the end of the source, so it's always
+/// a char boundary even if the escape is malformed.
+fn escape_end(src: &str, ix: usize) -> Option<usize> {
+    let b = src.as_bytes();
+    let mut end = ix + 1;
+    if end >= b.len() || b[end] != b'u' { return None }
+    // Any number of 'u's is allowed
+    while end < b.len() && b[end] == b'u' { end += 1; }
+    Some(src[end..].char_indices().nth(4).map_or(src.len(), |(i, _)| end + i))
+}
+
+/// If `offset` is inside a unicode escape, returns the start of the escape.
+/// Otherwise returns `offset`.
+pub fn escape_start(src: &str, offset: usize) -> usize {
+    let b = src.as_bytes();
+    // Look back over any hex digits and 'u's for the escape's '\'
+    let mut ix = offset;
+    while ix > 0 && offset - ix < 4 && b[ix - 1].is_ascii_hexdigit() { ix -= 1; }
+    while ix > 0 && b[ix - 1] == b'u' { ix -= 1; }
+    if ix == 0 || b[ix - 1] != b'\\' {
+        return offset;
+    }
+    let start = ix - 1;
+    let backslashes = b[..start].iter().rev().take_while(|&&c| c == b'\\').count();
+    match escape_end(src, start) {
+        Some(end) if backslashes % 2 == 0 && end > offset => start,
+        _ => offset,
+    }
+}
+
+/// Parses the 4 hex digits of an escape.
+fn parse_escape(src: &str, start: usize, end: usize) -> Result<u32, LexErr> {
+    let err = || LexErr::Span("Malformed unicode escape".to_owned(), Point(start), Point(end));
+    // If the escape is cut short or has non-ASCII chars, these 4 bytes aren't
+    // all hex digits
+    let digits = end.checked_sub(4).and_then(|ix| src.get(ix..end)).ok_or_else(err)?;
+    if !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(err());
+    }
+    Ok(u32::from_str_radix(digits, 16).unwrap())
+}
+
+/// Translates the escape at `ix..end`, along with the low half of a surrogate
+/// pair if there is one. Returns the char and the end of the escape(s).
+fn translate_escape(src: &str, ix: usize, end: usize) -> Result<(char, usize), LexErr> {
+    let mut code = parse_escape(src, ix, end)?;
+    let mut esc_end = end;
+    // UTF-16 surrogate pairs are written as 2 consecutive escapes
+    if (0xD800..0xDC00).contains(&code) {
+        if let Some(low_end) = escape_end(src, esc_end) {
+            let low = parse_escape(src, esc_end, low_end)?;
+            if (0xDC00..0xE000).contains(&low) {
+                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                esc_end = low_end;
+            }
+        }
+    }
+    let c = ::std::char::from_u32(code).ok_or_else(
+        || LexErr::Span("Unpaired surrogate in unicode escape".to_owned(),
+                        Point(ix), Point(esc_end)))?;
+    Ok((c, esc_end))
+}
+
+impl<'a> Translated<'a> {
+    /// Translate all unicode escapes in the given source. Errors if an escape is
+    /// malformed, or if surrogate escapes don't pair up into a valid char.
+    pub fn new(src: &'a str) -> Result<Translated<'a>, LexErr> {
+        Translated::translate(src, 0, src.len(), false)
+    }
+
+    /// Translate all valid unicode escapes in the given source, leaving any
+    /// invalid ones untranslated. Their errors can be found with
+    /// `bad_escape_in`.
+    pub fn new_tolerant(src: &'a str) -> Translated<'a> {
+        Translated::new_tolerant_part(src, 0, src.len())
+    }
+
+    /// Like `new_tolerant`, but only translates `src[start..end]`. Offsets into
+    /// the original source are still offsets into all of `src`. The range
+    /// shouldn't cut a unicode escape in two (see `escape_start`).
+    pub fn new_tolerant_part(src: &'a str, start: usize, end: usize) -> Translated<'a> {
+        Translated::translate(src, start, end, true).unwrap()
+    }
+
+    fn translate(full_src: &'a str, base: usize, end: usize, tolerant: bool)
+                 -> Result<Translated<'a>, LexErr> {
+        let src = &full_src[base..end];
+        let b = src.as_bytes();
+        // Fast path - most source contains no escapes at all
+        if !src.contains("\\u") {
+            return Ok(Translated { text: Cow::Borrowed(src), base, escapes: Vec::new(),
+                                   bad_escapes: Vec::new() });
+        }
+        // Errors are relative to 'src' until they're moved onto 'full_src'
+        let shift = |e| match e {
+            LexErr::Span(s, start, end) =>
+                LexErr::Span(s, Point(base + start.0), Point(base + end.0)),
+            e => e,
+        };
+
+        let mut text = String::with_capacity(src.len());
+        let mut escapes = Vec::new();
+        let mut bad_escapes = Vec::new();
+        // Start of the source we haven't copied into 'text' yet
+        let mut copied = 0;
+        let mut ix = 0;
+        // Number of backslashes directly before 'ix'. A '\' is only eligible to
+        // start an escape if this is even, so "\\u0041" is left alone.
+        let mut backslashes = full_src[..base].bytes().rev()
+            .take_while(|&c| c == b'\\').count();
+        while ix < b.len() {
+            if b[ix] != b'\\' {
+                backslashes = 0;
+                ix += 1;
+                continue;
+            }
+            let end = match escape_end(src, ix) {
+                Some(end) if backslashes % 2 == 0 => end,
+                _ => {
+                    backslashes += 1;
+                    ix += 1;
+                    continue;
+                }
+            };
+            let (c, esc_end) = match translate_escape(src, ix, end) {
+                Ok(res) => res,
+                Err(err) if tolerant => {
+                    let err = shift(err);
+                    let esc_end = match err { LexErr::Span(_, _, end) => end.0, _ => base + end };
+                    bad_escapes.push(BadEscape { start: base + ix, end: esc_end, err });
+                    backslashes += 1;
+                    ix += 1;
+                    continue;
+                }
+                Err(e) => return Err(shift(e)),
+            };
+            text.push_str(&src[copied..ix]);
+            let start = text.len();
+            text.push(c);
+            escapes.push(Escape { start, end: text.len(), orig_start: base + ix,
+                                  orig_end: base + esc_end });
+            copied = esc_end;
+            ix = esc_end;
+            // Translated chars never take part in another escape (JLS 3.3), so
+            // a translated '\' doesn't count here
+            backslashes = 0;
+        }
+        text.push_str(&src[copied..]);
+        Ok(Translated { text: Cow::Owned(text), base, escapes, bad_escapes })
+    }
+
+    /// Maps an offset in the translated text back to an offset in the original
+    /// source. Offsets inside a translated char map to the start of its escape.
+    pub fn orig_offset(&self, offset: usize) -> usize {
+        // Find the last escape starting at or before the offset
+        match self.escapes.binary_search_by(|e| e.start.cmp(&offset)) {
+            Ok(ix) => self.escapes[ix].orig_start,
+            Err(0) => self.base + offset,
+            Err(ix) => {
+                let e = self.escapes[ix - 1];
+                if offset < e.end {
+                    e.orig_start
+                } else {
+                    e.orig_end + (offset - e.end)
+                }
+            }
+        }
+    }
+
+    /// Maps an offset in the original source to an offset in the translated
+    /// text. Offsets inside an escape map to the start of its char.
+    pub fn translated_offset(&self, orig: usize) -> usize {
+        match self.escapes.binary_search_by(|e| e.orig_start.cmp(&orig)) {
+            Ok(ix) => self.escapes[ix].start,
+            Err(0) => orig - self.base,
+            Err(ix) => {
+                let e = self.escapes[ix - 1];
+                if orig < e.orig_end {
+                    e.start
+                } else {
+                    e.end + (orig - e.orig_end)
+                }
+            }
+        }
+    }
+
+    /// Returns the first escape which was left untranslated in the given range
+    /// of the original source, as its end and its error.
+    pub fn bad_escape_in(&self, start: usize, end: usize) -> Option<(usize, &LexErr)> {
+        let ix = match self.bad_escapes.binary_search_by(|e| e.start.cmp(&start)) {
+            Ok(ix) | Err(ix) => ix,
+        };
+        match self.bad_escapes.get(ix) {
+            Some(e) if e.start < end => Some((e.end, &e.err)),
+            _ => None,
+        }
+    }
+
+    /// Maps a token lexed from the translated text back onto the original
+    /// source.
+    pub fn orig_token(&self, tok: Token) -> Token {
+        if self.escapes.is_empty() && self.base == 0 { return tok }
+        Token {
+            start: Point(self.orig_offset(tok.start.0)),
+            end: Point(self.orig_offset(tok.end.0)),
+            full_start: Point(self.orig_offset(tok.full_start.0)),
+            full_end: Point(self.orig_offset(tok.full_end.0)),
+            .. tok
+        }
+    }
+
+    /// Maps the span of an error from the translated text back onto the
+    /// original source.
+    pub fn orig_err(&self, e: LexErr) -> LexErr {
+        match e {
+            LexErr::Span(s, start, end) =>
+                LexErr::Span(s, Point(self.orig_offset(start.0)), Point(self.orig_offset(end.0))),
+            e => e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        let src = "int \\u0061 = '\\u005c\\u005c'; // \\uuu00e9";
+        let t = Translated::new(src).unwrap();
+        assert_eq!(t.text, "int a = '\\\\'; // \u{e9}");
+        // 'a' maps back to the start of its escape, and the '=' after it to
+        // just past the escape
+        assert_eq!(t.orig_offset(4), 4);
+        assert_eq!(t.orig_offset(6), 11);
+        assert_eq!(t.orig_offset(t.text.len()), src.len());
+
+        // Escaped backslashes don't start escapes, and translated ones don't
+        // start another escape
+        let src = "\"\\\\u0041\" \\u005cu0041";
+        assert_eq!(Translated::new(src).unwrap().text, "\"\\\\u0041\" \\u0041");
+
+        // Surrogate pairs combine into a single char
+        assert_eq!(Translated::new("\\uD83D\\uDE00").unwrap().text, "\u{1F600}");
+    }
+
+    #[test]
+    fn test_translate_err() {
+        match Translated::new("foo \\u00g1") {
+            Err(LexErr::Span(_, start, end)) => assert_eq!((start.0, end.0), (4, 10)),
+            res => panic!("Expected error, got {:?}", res),
+        }
+        assert!(Translated::new("\\u12").is_err());
+        assert!(Translated::new("\\uD800").is_err());
+
+        // Tolerant translation leaves bad escapes alone
+        let t = Translated::new_tolerant("\\u0061 \\u00g1 \\uD800 \\u0061");
+        assert_eq!(t.text, "a \\u00g1 \\uD800 a");
+        assert_eq!(t.bad_escape_in(0, 8), Some((13, &LexErr::Span("Malformed unicode escape".to_owned(),
+                                                                   Point(7), Point(13)))));
+        assert_eq!(t.bad_escape_in(8, 15).map(|(end, _)| end), Some(20));
+        assert_eq!(t.bad_escape_in(20, 27), None);
+        assert_eq!(t.translated_offset(7), 2);
+        assert_eq!(t.translated_offset(3), 0);
+
+        // Malformed escapes end on a char boundary
+        let t = Translated::new_tolerant("\\u\u{20ac}\u{e9} x;");
+        assert_eq!(t.bad_escape_in(0, 1).map(|(end, _)| end), Some(9));
+        let t = Translated::new_tolerant("a \\uu\u{e9}");
+        assert_eq!(t.bad_escape_in(0, 3).map(|(end, _)| end), Some(t.text.len()));
+    }
+
+    #[test]
+    fn test_translate_part() {
+        let src = "a\\\\u0041 \\u0042\\u0043";
+        // The '\' at the start is escaped by the one before it
+        let t = Translated::new_tolerant_part(src, 2, 15);
+        assert_eq!(t.text, "\\u0041 B");
+        assert_eq!(t.orig_offset(0), 2);
+        assert_eq!(t.orig_offset(t.text.len()), 15);
+        assert_eq!(t.translated_offset(15), t.text.len());
+
+        let t = Translated::new_tolerant_part(src, 9, src.len());
+        assert_eq!(t.text, "BC");
+        assert_eq!(t.orig_offset(1), 15);
+        // Escapes cut short by the end of the part are left untranslated
+        let t = Translated::new_tolerant_part(src, 12, 18);
+        assert_eq!(t.bad_escape_in(15, 16).map(|(end, _)| end), Some(18));
+    }
+
+    #[test]
+    fn test_escape_start() {
+        let src = "a\\u0041\\\\u0042 \\uu00";
+        let starts: Vec<_> = (0..src.len() + 1).map(|ix| escape_start(src, ix)).collect();
+        assert_eq!(starts, vec![0, 1, 1, 1, 1, 1, 1, 7, 8, 9, 10, 11, 12, 13, 14, 15, 15, 15, 15,
+                                15, 20]);
+    }
+}
@@ -0,0 +1,144 @@
+//! Parsing for annotation type ('@interface') bodies
+
+use super::*;
+use lexer::TokenType;
+use super::annotations::parse_element_value;
+use super::compilation_units::{is_annotation_type_next, is_class_or_interface_next,
+                               parse_class_declaration, parse_interface_declaration};
+use super::interfaces::parse_constant_declarators_rest;
+use super::modifiers::{is_modifier_or_annot, is_sealed_key, parse_class_modifier,
+                       parse_modifier};
+use super::records::is_record_next;
+use super::types::parse_type;
+
+#[allow(dead_code)]
+pub fn parse_annotation_type_body(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    assert_term(tokens, src, TokenKind::LBrace)?;
+    let mut children = Vec::new();
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::RBrace => (),
+        _ => children.push(parse_annotation_type_element_declarations(tokens, src)?),
+    }
+    assert_term(tokens, src, TokenKind::RBrace)?;
+    Ok(nterm(NTermType::AnnotationTypeBody, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_annotation_type_element_declarations(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        match tok.kind {
+            TokenKind::RBrace => break,
+            _ => children.push(parse_annotation_type_element_declaration(tokens, src)?),
+        }
+    }
+    Ok(nterm(NTermType::AnnotationTypeElementDeclarations, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_annotation_type_element_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let children = match tokens.clone().next() {
+        // A stray ';' is allowed between elements
+        Some(tok) if tok.kind == TokenKind::Semi => vec![term(tokens.next().unwrap())],
+        _ => {
+            let mut children = Vec::new();
+            while let Some(tok) = tokens.clone().next() {
+                if is_modifier_or_annot(&tok) && !is_annotation_type_next(tokens) {
+                    children.push(parse_modifier(tokens, src)?);
+                } else if is_sealed_key(&tok) && is_class_or_interface_next(tokens, src) {
+                    children.push(parse_class_modifier(tokens, src)?);
+                } else { break }
+            }
+            children.push(parse_annotation_type_element_rest(tokens, src)?);
+            children
+        }
+    };
+    Ok(nterm(NTermType::AnnotationTypeElementDeclaration, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_annotation_type_element_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let tok = tokens.clone().next().ok_or(
+        ParseErr::Raw("Unexpected EOF, expected annotation type element".to_owned()))?;
+    let children = match tok.kind {
+        TokenKind::Class | TokenKind::Enum => vec![parse_class_declaration(tokens, src)?],
+        TokenKind::Ident if is_record_next(tokens) => vec![parse_class_declaration(tokens, src)?],
+        TokenKind::Interface | TokenKind::At => vec![parse_interface_declaration(tokens, src)?],
+        _ => vec![
+            parse_type(tokens, src)?,
+            assert_term_with_type(tokens, TokenType::Ident)?,
+            parse_annotation_method_or_constant_rest(tokens, src)?,
+            assert_term(tokens, src, TokenKind::Semi)?],
+    };
+    Ok(nterm(NTermType::AnnotationTypeElementRest, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_annotation_method_or_constant_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let child = match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::LParen => parse_annotation_method_rest(tokens, src)?,
+        _ => parse_constant_declarators_rest(tokens, src)?,
+    };
+    Ok(nterm(NTermType::AnnotationMethodOrConstantRest, vec![child]))
+}
+
+#[allow(dead_code)]
+pub fn parse_annotation_method_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![assert_term(tokens, src, TokenKind::LParen)?,
+                            assert_term(tokens, src, TokenKind::RParen)?];
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::LBracket {
+            children.push(term(tokens.next().unwrap()));
+            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
+        }
+    }
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Default {
+            children.push(term(tokens.next().unwrap()));
+            children.push(parse_element_value(tokens, src)?);
+        }
+    }
+    Ok(nterm(NTermType::AnnotationMethodRest, children))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    #[test]
+    fn test_parse_annotation_type_body() {
+        let src = "{
+            String value();
+            int[] ids() default {1, 2};
+            Class<?> type() default Object.class;
+            @Deprecated String name() default \"\";
+            Retention retention() default @Retention(RetentionPolicy.RUNTIME);
+            int MAX = 10, MIN = 0;
+            ;
+            enum Level { LOW, HIGH }
+            @interface Inner {}
+        }";
+        let node = parse_annotation_type_body(&mut TokenIter::new(&lex(src, "").unwrap()), src)
+            .unwrap();
+        let decls = &node.children[0];
+        assert_eq!(decls.children.len(), 9);
+        // 'default' and its ElementValue
+        let method = &decls.children[1].children[0].children[2].children[0];
+        assert_eq!(method.node_type, NodeType::NTerm(NTermType::AnnotationMethodRest));
+        assert_eq!(method.children.len(), 4);
+        assert_eq!(method.children[3].node_type, NodeType::NTerm(NTermType::ElementValue));
+        // The annotation on 'name' is a modifier
+        assert_eq!(decls.children[3].children.len(), 2);
+        let constants = &decls.children[5].children[0].children[2].children[0];
+        assert_eq!(constants.node_type, NodeType::NTerm(NTermType::ConstantDeclaratorsRest));
+    }
+
+    #[test]
+    fn test_parse_empty_annotation_type_body() {
+        let src = "{}";
+        let node = parse_annotation_type_body(&mut TokenIter::new(&lex(src, "").unwrap()), src)
+            .unwrap();
+        assert!(node.children.is_empty());
+    }
+}
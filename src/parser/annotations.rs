@@ -5,18 +5,18 @@ use lexer::TokenType;
 
 #[allow(dead_code)]
 pub fn parse_annotation(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    let mut children = vec![assert_term(tokens, src, "@")?,
+    let mut children = vec![assert_term(tokens, src, TokenKind::At)?,
                         parse_qualified_identifier(tokens, src)?];
 
     let mut clone = tokens.clone();
     match clone.next() {
-        Some(tok) if tok.val(src) == "(" => {
-            children.push(term(*tokens.next().unwrap()));
+        Some(tok) if tok.kind == TokenKind::LParen => {
+            children.push(term(tokens.next().unwrap()));
             match clone.next() {
-                Some(tok) if tok.val(src) == ")" => children.push(term(*tokens.next().unwrap())),
+                Some(tok) if tok.kind == TokenKind::RParen => children.push(term(tokens.next().unwrap())),
                 _ => {
                     children.push(parse_annotation_element(tokens, src)?);
-                    children.push(assert_term(tokens, src, ")")?);
+                    children.push(assert_term(tokens, src, TokenKind::RParen)?);
                 }
             }
         }
@@ -30,7 +30,7 @@ pub fn parse_annotation(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_annotations(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_annotation(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "@" {
+        if tok.kind == TokenKind::At {
             children.push(parse_annotation(tokens, src)?);
         } else {
             break;
@@ -43,7 +43,7 @@ pub fn parse_annotations(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_element_value_pair(tokens: &mut TokenIter, src: &str) -> ParseRes {
     Ok(nterm(NTermType::ElementValuePair, vec![
         assert_term_with_type(tokens, TokenType::Ident)?,
-        assert_term(tokens, src, "=")?,
+        assert_term(tokens, src, TokenKind::Eq)?,
         parse_element_value(tokens, src)?,
         ]))
 }
@@ -52,7 +52,7 @@ pub fn parse_element_value_pair(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_element_value_pairs(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_element_value_pair(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
+        if tok.kind == TokenKind::Comma {
             tokens.next(); // Skip ','
             children.push(parse_element_value_pair(tokens, src)?);
         } else {
@@ -65,12 +65,16 @@ pub fn parse_element_value_pairs(tokens: &mut TokenIter, src: &str) -> ParseRes
 #[allow(dead_code)]
 pub fn parse_element_values(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_element_value(tokens, src)?];
-    while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
-            tokens.next(); // Skip ','
-            children.push(parse_element_value(tokens, src)?);
-        } else {
-            break;
+    loop {
+        // A ',' followed by '}' is a trailing comma, not another value
+        let mut clone = tokens.clone();
+        match (clone.next(), clone.next()) {
+            (Some(comma), Some(tok)) if comma.kind == TokenKind::Comma
+                && tok.kind != TokenKind::RBrace => {
+                tokens.next(); // Skip ','
+                children.push(parse_element_value(tokens, src)?);
+            }
+            _ => break,
         }
     }
     Ok(nterm(NTermType::ElementValues, children))
@@ -78,10 +82,19 @@ pub fn parse_element_values(tokens: &mut TokenIter, src: &str) -> ParseRes {
 
 #[allow(dead_code)]
 pub fn parse_element_value_array_initializer(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    let mut children = vec![assert_term(tokens, src, "{")?];
+    let mut children = vec![assert_term(tokens, src, TokenKind::LBrace)?];
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "}" => children.push(term(*tokens.next().unwrap())),
-        _ => children.push(parse_element_values(tokens, src)?),
+        Some(tok) if tok.kind == TokenKind::RBrace => children.push(term(tokens.next().unwrap())),
+        _ => {
+            children.push(parse_element_values(tokens, src)?);
+            // The values may have a trailing ','
+            if let Some(tok) = tokens.clone().next() {
+                if tok.kind == TokenKind::Comma {
+                    tokens.next(); // Skip ','
+                }
+            }
+            children.push(assert_term(tokens, src, TokenKind::RBrace)?);
+        }
     }
     Ok(nterm(NTermType::ElementValueArrayInitializer, children))
 }
@@ -90,8 +103,8 @@ pub fn parse_element_value_array_initializer(tokens: &mut TokenIter, src: &str)
 pub fn parse_element_value(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut clone = tokens.clone();
     Ok(nterm(NTermType::ElementValue, vec![match clone.next() {
-        Some(tok) if tok.val(src) == "@" => parse_annotation(tokens, src)?,
-        Some(tok) if tok.val(src) == "{" => parse_element_value_array_initializer(tokens, src)?,
+        Some(tok) if tok.kind == TokenKind::At => parse_annotation(tokens, src)?,
+        Some(tok) if tok.kind == TokenKind::LBrace => parse_element_value_array_initializer(tokens, src)?,
         _ => parse_expression1(tokens, src)?,
     }]))
 }
@@ -101,7 +114,7 @@ pub fn parse_annotation_element(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut clone = tokens.clone();
     let child = match clone.next() {
         Some(tok) if tok.token_type == TokenType::Ident => match clone.next() {
-            Some(tok) if tok.val(src) == "=" =>
+            Some(tok) if tok.kind == TokenKind::Eq =>
                 parse_element_value_pairs(tokens, src)?,
             _ => parse_element_value(tokens, src)?,
         }
@@ -118,19 +131,19 @@ mod tests {
     #[test]
     fn test_parse_annotation() {
         let src = "@MyAnnotation";
-        let node = parse_annotation(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_annotation(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
         assert_eq!(node.children[0].val(src).unwrap(), "@");
 
         let src = "@MyAnnotation()";
-        let node = parse_annotation(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_annotation(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 4);
         assert_eq!(node.children[0].val(src).unwrap(), "@");
         assert_eq!(node.children[2].val(src).unwrap(), "(");
         assert_eq!(node.children[3].val(src).unwrap(), ")");
 
         let src = "@MyAnnotation(someVal = @Hello)";
-        let node = parse_annotation(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_annotation(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 5);
         assert_eq!(node.children[0].val(src).unwrap(), "@");
         assert_eq!(node.children[2].val(src).unwrap(), "(");
@@ -141,14 +154,14 @@ mod tests {
     fn test_parse_annotations() {
         let src = "@MyAnnotation
 @OtherAnnotation";
-        let node = parse_annotation(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_annotation(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
     }
 
     #[test]
     fn test_parse_annotation_element() {
         let src = "ident = {@Annot, @OtherAnnot}";
-        let node = parse_annotation_element(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_annotation_element(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         match node.children[0].node_type {
             NodeType::NTerm(NTermType::ElementValuePairs) => (),
@@ -156,11 +169,22 @@ mod tests {
         }
 
         let src = "{@Annot, @OtherAnnot}";
-        let node = parse_annotation_element(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_annotation_element(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         match node.children[0].node_type {
             NodeType::NTerm(NTermType::ElementValue) => (),
             _ => panic!("Wrong nterm type"),
         }
     }
+
+    #[test]
+    fn test_parse_element_value_array_initializer() {
+        for src in &["{}", "{1}", "{\"a\", \"b\",}", "{@A, {2}}"] {
+            let tokens = lex(src, "").unwrap();
+            let mut tokens = TokenIter::new(&tokens);
+            let node = parse_element_value_array_initializer(&mut tokens, src).unwrap();
+            assert_eq!(node.children.last().unwrap().val(src), Some("}"));
+            assert!(tokens.next().is_none());
+        }
+    }
 }
@@ -12,19 +12,20 @@ pub fn parse_literal(tokens: &mut TokenIter, _src: &str) -> ParseRes {
             tok.token_type == TokenType::IntLit ||
             tok.token_type == TokenType::FloatLit ||
             tok.token_type == TokenType::StringLit ||
+            tok.token_type == TokenType::TextBlock ||
             tok.token_type == TokenType::CharLit ||
-            tok.token_type == TokenType::BoolLit => Ok(nterm(NTermType::Literal, vec![term(*tok)])),
-        Some(tok) => Err(ParseErr::Point("Expected literal".to_owned(), *tok)),
+            tok.token_type == TokenType::BoolLit => Ok(nterm(NTermType::Literal, vec![term(tok)])),
+        Some(tok) => Err(ParseErr::Point("Expected literal".to_owned(), tok)),
         None => Err(ParseErr::Raw("Expected literal, got EOF".to_owned()))
     }
 }
 
 #[allow(dead_code)]
 pub fn parse_arguments(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    let mut children = vec![assert_term(tokens, src, "(")?];
+    let mut children = vec![assert_term(tokens, src, TokenKind::LParen)?];
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == ")" => {
-            children.push(term(*tokens.next().unwrap()));
+        Some(tok) if tok.kind == TokenKind::RParen => {
+            children.push(term(tokens.next().unwrap()));
             return Ok(nterm(NTermType::Arguments, children));
         }
         _ => children.push(parse_expression(tokens, src)?),
@@ -34,8 +35,8 @@ pub fn parse_arguments(tokens: &mut TokenIter, src: &str) -> ParseRes {
         let mut clone = tokens.clone();
         let tok = clone.next();
         match tok {
-            Some(tok) if tok.val(src) == ")" => break,
-            Some(tok) if tok.val(src) == "," => match clone.next() {
+            Some(tok) if tok.kind == TokenKind::RParen => break,
+            Some(tok) if tok.kind == TokenKind::Comma => match clone.next() {
                 Some(_) => {
                     tokens.next().unwrap();
                     children.push(parse_expression(tokens, src)?);
@@ -43,37 +44,37 @@ pub fn parse_arguments(tokens: &mut TokenIter, src: &str) -> ParseRes {
                 None => return Err(ParseErr::Raw("Expected expression, got EOF".to_owned())),
             }
             None => return Err(ParseErr::Raw("Unexpected EOF in expression".to_owned())),
-            Some(tok) => return Err(ParseErr::Point("Expected ')' or ','".to_owned(), *tok)),
+            Some(tok) => return Err(ParseErr::Point("Expected ')' or ','".to_owned(), tok)),
         }
     }
-    children.push(assert_term(tokens, src, ")")?);
-    Ok(nterm(NTermType::ParExpression, children))
+    children.push(assert_term(tokens, src, TokenKind::RParen)?);
+    Ok(nterm(NTermType::Arguments, children))
 }
 
 #[allow(dead_code)]
 pub fn parse_par_expression(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    let mut children = vec![assert_term(tokens, src, "(")?];
+    let mut children = vec![assert_term(tokens, src, TokenKind::LParen)?];
     loop {
         let tok = tokens.clone().next();
         match tok {
-            Some(tok) if tok.val(src) == ")" => break,
+            Some(tok) if tok.kind == TokenKind::RParen => break,
             None => return Err(ParseErr::Raw("Unexpected EOF in expression".to_owned())),
             _ => children.push(parse_expression(tokens, src)?),
         }
     }
-    children.push(assert_term(tokens, src, ")")?);
+    children.push(assert_term(tokens, src, TokenKind::RParen)?);
     Ok(nterm(NTermType::ParExpression, children))
 }
 
 #[allow(dead_code)]
 pub fn parse_super_suffix(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "." => {
+        Some(tok) if tok.kind == TokenKind::Dot => {
             let mut children = vec![
-                term(*tokens.next().unwrap()),
+                term(tokens.next().unwrap()),
                 assert_term_with_type(tokens, TokenType::Ident)?];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "(" => {
+                Some(tok) if tok.kind == TokenKind::LParen => {
                     children.push(parse_arguments(tokens, src)?);
                 }
                 _ => ()
@@ -88,13 +89,13 @@ pub fn parse_super_suffix(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_explicit_generic_invocation_suffix(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "super" => vec![
-            term(*tokens.next().unwrap()),
+        Some(tok) if tok.kind == TokenKind::Super => vec![
+            term(tokens.next().unwrap()),
             parse_super_suffix(tokens, src)?],
         Some(tok) if tok.token_type == TokenType::Ident => vec![
-            term(*tokens.next().unwrap()),
+            term(tokens.next().unwrap()),
             parse_arguments(tokens, src)?],
-        Some(tok) => return Err(ParseErr::Point("Expected 'super' or identifier".to_owned(), *tok)),
+        Some(tok) => return Err(ParseErr::Point("Expected 'super' or identifier".to_owned(), tok)),
         None => return Err(ParseErr::Raw("Expected 'super' or identifier, got EOF".to_owned())),
     };
     Ok(nterm(NTermType::ExplicitGenericInvocationSuffix, children))
@@ -111,84 +112,92 @@ pub fn parse_primary(tokens: &mut TokenIter, src: &str) -> ParseRes {
     // type=ident Identifier { . Identifier } [IdentifierSuffix]
     // valu=bt    BasicType {[]} . class
     // valu=void  void . class
-    let children = match tokens.clone().next() {
-        Some(tok) if tok.is_literal() => vec![term(*tokens.next().unwrap())],
-        Some(tok) if tok.val(src) == "(" => vec![parse_par_expression(tokens, src)?],
-        Some(tok) if tok.val(src) == "this" => {
-            let mut children = vec![term(*tokens.next().unwrap())];
+    let tok = match tokens.clone().next() {
+        Some(tok) => tok,
+        None => return Err(ParseErr::Raw("Expected type, literal, or value, got EOF".to_owned())),
+    };
+    let children = match tok.kind {
+        TokenKind::Literal => vec![term(tokens.next().unwrap())],
+        TokenKind::LParen => vec![parse_par_expression(tokens, src)?],
+        TokenKind::This => {
+            let mut children = vec![term(tokens.next().unwrap())];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "(" => children.push(parse_arguments(tokens, src)?),
+                Some(tok) if tok.kind == TokenKind::LParen => children.push(parse_arguments(tokens, src)?),
                 _ => ()
             }
             children
         }
-        Some(tok) if tok.val(src) == "super" => vec![
-            term(*tokens.next().unwrap()),
+        TokenKind::Super => vec![
+            term(tokens.next().unwrap()),
             parse_super_suffix(tokens, src)?],
-        Some(tok) if tok.val(src) == "new" => vec![
-            term(*tokens.next().unwrap()),
+        TokenKind::New => vec![
+            term(tokens.next().unwrap()),
             parse_creator(tokens, src)?],
-        Some(tok) if tok.val(src) == "<" => {
+        TokenKind::Lt => {
             let mut children = vec![parse_non_wildcard_type_arguments(tokens, src)?];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "this" => {
-                    children.push(term(*tokens.next().unwrap()));
+                Some(tok) if tok.kind == TokenKind::This => {
+                    children.push(term(tokens.next().unwrap()));
                     children.push(parse_arguments(tokens, src)?);
                 }
                 _ => children.push(parse_explicit_generic_invocation_suffix(tokens, src)?)
             }
             children
         }
-        Some(tok) if tok.token_type == TokenType::Ident => {
-            let mut children = vec![term(*tokens.next().unwrap())];
-            while let Some(tok) = tokens.clone().next() {
-                if tok.val(src) == "." {
-                    tokens.next().unwrap();
-                    children.push(assert_term_with_type(tokens, TokenType::Ident)?);
-                } else {
-                    break
+        TokenKind::Ident => {
+            let mut children = vec![term(tokens.next().unwrap())];
+            // A '.' not followed by an identifier starts the IdentifierSuffix,
+            // e.g. 'Foo.class' or 'Foo.this'
+            loop {
+                let mut clone = tokens.clone();
+                match (clone.next(), clone.next()) {
+                    (Some(dot), Some(ident)) if dot.kind == TokenKind::Dot
+                        && ident.token_type == TokenType::Ident => {
+                        tokens.next().unwrap();
+                        children.push(term(tokens.next().unwrap()));
+                    }
+                    _ => break,
                 }
             }
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "(" || tok.val(src) == "." =>
+                Some(tok) if tok.kind == TokenKind::LParen || tok.kind == TokenKind::Dot =>
                     children.push(parse_identifier_suffix(tokens, src)?),
                 _ => ()
             }
             children
         }
-        Some(tok) if is_basic_type(tok.val(src)) => {
+        kind if is_basic_type(kind) => {
                 let mut children = vec![parse_basic_type(tokens, src)?];
                 // Consume all []
                 let mut consumed = 0;
                 let mut clone = tokens.clone();
                 while let Some(tok) = clone.next() {
-                    if tok.val(src) == "[" {
+                    if tok.kind == TokenKind::LBracket {
                         if let Some(tok) = clone.next() {
-                            if tok.val(src) == "]" {
+                            if tok.kind == TokenKind::RBracket {
                                 consumed += 2
                             } else {
-                                return Err(ParseErr::Point("Mismatched []".to_owned(), *tok));
+                                return Err(ParseErr::Point("Mismatched []".to_owned(), tok));
                             }
                         } else {
-                            return Err(ParseErr::Point("Mismatched []".to_owned(), *tok));
+                            return Err(ParseErr::Point("Mismatched []".to_owned(), tok));
                         }
                     } else {
                         break;
                     }
                 }
-                for _ in 0..consumed { children.push(term(*tokens.next().unwrap())); }
-                children.push(assert_term(tokens, src, ".")?);
-                children.push(assert_term(tokens, src, "class")?);
+                for _ in 0..consumed { children.push(term(tokens.next().unwrap())); }
+                children.push(assert_term(tokens, src, TokenKind::Dot)?);
+                children.push(assert_term(tokens, src, TokenKind::Class)?);
                 children
             }
-        Some(tok) if tok.val(src) == "void" =>
+        TokenKind::Void =>
             vec![
-                assert_term(tokens, src, "void")?,
-                assert_term(tokens, src, ".")?,
-                assert_term(tokens, src, "class")?
+                assert_term(tokens, src, TokenKind::Void)?,
+                assert_term(tokens, src, TokenKind::Dot)?,
+                assert_term(tokens, src, TokenKind::Class)?
             ],
-        Some(tok) => return Err(ParseErr::Point("Expected type, literal, or value".to_owned(), *tok)),
-        None => return Err(ParseErr::Raw("Expected type, literal, or value, got EOF".to_owned())),
+        _ => return Err(ParseErr::Point("Expected type, literal, or value".to_owned(), tok)),
     };
     Ok(nterm(NTermType::Primary, children))
 }
@@ -203,60 +212,60 @@ mod tests {
     fn test_parse_literal() {
         let src = ["24.0", "24", "null", "\"Hello\"", "'a'", "true"];
         assert!(src.iter().all(|src| {
-            parse_literal(&mut lex(src, "").unwrap().iter(), src).is_ok()
+            parse_literal(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok()
         }));
     }
 
     #[test]
     fn test_parse_par_expression() {
         let src = "()";
-        let node = parse_par_expression(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_par_expression(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
 
         let src = "(foo + bar)";
-        let node = parse_par_expression(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_par_expression(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
     }
 
     #[test]
     fn test_parse_arguments() {
         let src = "()";
-        let node = parse_arguments(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_arguments(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
 
         let src = "(foo, bar, foo + bar)";
-        let node = parse_arguments(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_arguments(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 5);
     }
 
     #[test]
     fn test_parse_super_suffix() {
         let src = ".foo";
-        let node = parse_super_suffix(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_super_suffix(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
 
         let src = ".foo()";
-        let node = parse_super_suffix(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_super_suffix(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
 
         let src = "()";
-        let node = parse_super_suffix(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_super_suffix(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
 
         let src = "(foo, bar)";
-        let node = parse_super_suffix(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_super_suffix(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
     }
 
     #[test]
     fn test_parse_explicit_generic_invocation_suffix() {
         let src = "super.foo()";
-        let node = parse_explicit_generic_invocation_suffix(&mut lex(src, "").unwrap().iter(),
+        let node = parse_explicit_generic_invocation_suffix(&mut TokenIter::new(&lex(src, "").unwrap()),
                                                             src).unwrap();
         assert_eq!(node.children.len(), 2);
 
         let src = "foo()";
-        let node = parse_explicit_generic_invocation_suffix(&mut lex(src, "").unwrap().iter(),
+        let node = parse_explicit_generic_invocation_suffix(&mut TokenIter::new(&lex(src, "").unwrap()),
                                                             src).unwrap();
         assert_eq!(node.children.len(), 2);
     }
@@ -264,11 +273,16 @@ mod tests {
     #[test]
     fn test_parse_primary() {
         let src = "1.0";
-        let node = parse_primary(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_primary(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
 
         let src = "boolean.class";
-        let node = parse_primary(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_primary(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
+
+        let src = "java.lang.Object.class";
+        let node = parse_primary(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 4);
+        assert_eq!(node.children[3].node_type, NodeType::NTerm(NTermType::IdentifierSuffix));
     }
 }
@@ -1,61 +1,181 @@
 use super::*;
+use lexer::TokenType;
+use super::compilation_units::{is_annotation_type_next, is_class_or_interface_next,
+                               parse_class_declaration, parse_interface_declaration};
+use super::formal_parameters::parse_formal_parameters;
+use super::identifiers::parse_qualified_identifier_list;
 use super::statements::parse_block;
-use super::modifiers::{is_modifier_or_annot, parse_modifier};
+use super::modifiers::{is_modifier_or_annot, is_sealed_key, parse_class_modifier,
+                       parse_modifier};
+use super::records::is_record_next;
+use super::types::{parse_type, parse_type_parameters};
+use super::variables::{parse_variable_declarator, parse_variable_declarator_rest};
+
+/// Parses '[throws QualifiedIdentifierList]' onto the end of `children`.
+pub fn parse_throws_maybe(tokens: &mut TokenIter, src: &str, children: &mut Vec<Node>)
+                          -> Result<(), ParseErr> {
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Throws {
+            children.push(term(tokens.next().unwrap()));
+            children.push(parse_qualified_identifier_list(tokens, src)?);
+        }
+    }
+    Ok(())
+}
+
+/// Parses '[throws QualifiedIdentifierList] (Block | ;)', the end of a method
+/// declaration, onto the end of `children`. Abstract and native methods have a
+/// ';' rather than a body.
+pub fn parse_method_body(tokens: &mut TokenIter, src: &str, children: &mut Vec<Node>)
+                         -> Result<(), ParseErr> {
+    parse_throws_maybe(tokens, src, children)?;
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Semi => children.push(term(tokens.next().unwrap())),
+        _ => children.push(parse_block(tokens, src)?),
+    }
+    Ok(())
+}
 
 #[allow(dead_code)]
-pub fn parse_void_method_declarator_rest(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_method_declarator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_formal_parameters(tokens, src)?];
+    // Legacy array dims after the parameters, e.g. 'int foo()[]'
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::LBracket {
+            children.push(term(tokens.next().unwrap()));
+            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
+        } else { break }
+    }
+    parse_method_body(tokens, src, &mut children)?;
+    Ok(nterm(NTermType::MethodDeclaratorRest, children))
 }
 
 #[allow(dead_code)]
-pub fn parse_constructor_declarator_rest(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_void_method_declarator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_formal_parameters(tokens, src)?];
+    parse_method_body(tokens, src, &mut children)?;
+    Ok(nterm(NTermType::VoidMethodDeclaratorRest, children))
 }
 
 #[allow(dead_code)]
-pub fn parse_generic_method_or_constructor_decl(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_constructor_declarator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_formal_parameters(tokens, src)?];
+    parse_throws_maybe(tokens, src, &mut children)?;
+    children.push(parse_block(tokens, src)?);
+    Ok(nterm(NTermType::ConstructorDeclaratorRest, children))
 }
 
 #[allow(dead_code)]
-pub fn parse_generic_method_or_constructor_rest(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_generic_method_or_constructor_decl(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    Ok(nterm(NTermType::GenericMethodOrConstructorDecl, vec![
+        parse_type_parameters(tokens, src)?,
+        parse_generic_method_or_constructor_rest(tokens, src)?]))
 }
 
 #[allow(dead_code)]
-pub fn parse_member_decl(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_generic_method_or_constructor_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut clone = tokens.clone();
+    let children = match (clone.next(), clone.next()) {
+        // Identifier ConstructorDeclaratorRest
+        (Some(ident), Some(paren)) if ident.token_type == TokenType::Ident
+            && paren.kind == TokenKind::LParen => vec![
+                term(tokens.next().unwrap()),
+                parse_constructor_declarator_rest(tokens, src)?],
+        // (Type | void) Identifier MethodDeclaratorRest
+        (Some(tok), _) => vec![
+            if tok.kind == TokenKind::Void {
+                term(tokens.next().unwrap())
+            } else {
+                parse_type(tokens, src)?
+            },
+            assert_term_with_type(tokens, TokenType::Ident)?,
+            parse_method_declarator_rest(tokens, src)?],
+        (None, _) => return Err(ParseErr::Raw(
+            "Unexpected EOF, expected generic method or constructor".to_owned())),
+    };
+    Ok(nterm(NTermType::GenericMethodOrConstructorRest, children))
 }
 
 #[allow(dead_code)]
-pub fn parse_method_or_field_decl(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_member_decl(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut clone = tokens.clone();
+    let tok = clone.next().ok_or(
+        ParseErr::Raw("Unexpected EOF, expected member declaration".to_owned()))?;
+    let children = match tok.kind {
+        TokenKind::Class | TokenKind::Enum => vec![parse_class_declaration(tokens, src)?],
+        TokenKind::Ident if is_record_next(tokens) => vec![parse_class_declaration(tokens, src)?],
+        TokenKind::Interface | TokenKind::At => vec![parse_interface_declaration(tokens, src)?],
+        TokenKind::Void => vec![
+            term(tokens.next().unwrap()),
+            assert_term_with_type(tokens, TokenType::Ident)?,
+            parse_void_method_declarator_rest(tokens, src)?],
+        TokenKind::Lt => vec![parse_generic_method_or_constructor_decl(tokens, src)?],
+        TokenKind::Ident => match clone.next() {
+            Some(tok) if tok.kind == TokenKind::LParen => vec![
+                term(tokens.next().unwrap()),
+                parse_constructor_declarator_rest(tokens, src)?],
+            _ => vec![parse_method_or_field_decl(tokens, src)?],
+        },
+        _ => vec![parse_method_or_field_decl(tokens, src)?],
+    };
+    Ok(nterm(NTermType::MemberDecl, children))
 }
 
 #[allow(dead_code)]
-pub fn parse_method_or_field_rest(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_method_or_field_decl(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    Ok(nterm(NTermType::MethodOrFieldDecl, vec![
+        parse_type(tokens, src)?,
+        assert_term_with_type(tokens, TokenType::Ident)?,
+        parse_method_or_field_rest(tokens, src)?]))
 }
 
 #[allow(dead_code)]
-pub fn parse_field_declarators_rest(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_method_or_field_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let children = match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::LParen =>
+            vec![parse_method_declarator_rest(tokens, src)?],
+        _ => vec![parse_field_declarators_rest(tokens, src)?,
+                  assert_term(tokens, src, TokenKind::Semi)?],
+    };
+    Ok(nterm(NTermType::MethodOrFieldRest, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_field_declarators_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_variable_declarator_rest(tokens, src)?];
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Comma {
+            tokens.next(); // Skip ','
+            children.push(parse_variable_declarator(tokens, src)?);
+        } else { break }
+    }
+    Ok(nterm(NTermType::FieldDeclaratorsRest, children))
+}
+
+/// Returns true if 'static' is followed by a block, i.e. it's a static
+/// initializer rather than a modifier.
+fn is_block_next(tokens: &TokenIter) -> bool {
+    let mut clone = tokens.clone();
+    clone.next();
+    matches!(clone.next(), Some(tok) if tok.kind == TokenKind::LBrace)
 }
 
 #[allow(dead_code)]
 pub fn parse_class_body_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "static" =>
-            vec![term(*tokens.next().unwrap()), // "static"
+        Some(tok) if tok.kind == TokenKind::Static && is_block_next(tokens) =>
+            vec![term(tokens.next().unwrap()), // "static"
                  parse_block(tokens, src)?],
-        Some(tok) if tok.val(src) == "{" => vec![parse_block(tokens, src)?],
-        Some(tok) if tok.val(src) == ";" => vec![term(*tokens.next().unwrap())],
+        Some(tok) if tok.kind == TokenKind::LBrace => vec![parse_block(tokens, src)?],
+        Some(tok) if tok.kind == TokenKind::Semi => vec![term(tokens.next().unwrap())],
         _ => {
             let mut children = Vec::new();
             // Parse modifier list
             while let Some(tok) = tokens.clone().next() {
-                if is_modifier_or_annot(tok.val(src)) {
+                if is_modifier_or_annot(&tok) && !is_annotation_type_next(tokens) {
                     children.push(parse_modifier(tokens, src)?);
+                } else if is_sealed_key(&tok) && is_class_or_interface_next(tokens, src) {
+                    children.push(parse_class_modifier(tokens, src)?);
                 } else { break }
             }
             children.push(parse_member_decl(tokens, src)?);
@@ -67,15 +187,15 @@ pub fn parse_class_body_declaration(tokens: &mut TokenIter, src: &str) -> ParseR
 
 #[allow(dead_code)]
 pub fn parse_class_body(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    assert_term(tokens, src, "{")?;
+    assert_term(tokens, src, TokenKind::LBrace)?;
     let mut children = Vec::new();
     while let Some(tok) = tokens.clone().next() {
-        match tok.val(src) {
-            "}" => break,
+        match tok.kind {
+            TokenKind::RBrace => break,
             _ => children.push(parse_class_body_declaration(tokens, src)?),
         }
     }
-    assert_term(tokens, src, "}")?;
+    assert_term(tokens, src, TokenKind::RBrace)?;
     Ok(nterm(NTermType::ClassBody, children))
 }
 
@@ -100,7 +220,56 @@ mod tests {
                 bar.add(\"Hello!\");
             }
         }";
-        let node = parse_class_body(&mut lex(src, "").unwrap().iter(), src).unwrap();
-        assert_eq!(node.children.len(), 5);
+        let node = parse_class_body(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 4);
+    }
+
+    #[test]
+    pub fn test_parse_member_decl() {
+        let src = "{
+            int a, b[] = {1}, c = 2;
+            static final String S = \"s\";
+            Foo(int a) throws Exception { this.a = a; }
+            <T> Foo(T t) {}
+            abstract int foo()[];
+            public <T extends Comparable<T>> void bar(T... ts) throws IOException, Error {}
+            private static class Inner<T> {}
+            interface I {}
+        }";
+        let node = parse_class_body(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 8);
+        let member_types: Vec<_> = node.children.iter()
+            .map(|decl| match decl.children.last().unwrap().children[0].node_type {
+                NodeType::NTerm(ref t) => Some(t.clone()),
+                NodeType::Term(_) => None,
+            })
+            .collect();
+        assert_eq!(member_types, vec![
+            Some(NTermType::MethodOrFieldDecl),
+            Some(NTermType::MethodOrFieldDecl),
+            None, // Constructor name
+            Some(NTermType::GenericMethodOrConstructorDecl),
+            Some(NTermType::MethodOrFieldDecl),
+            Some(NTermType::GenericMethodOrConstructorDecl),
+            Some(NTermType::ClassDeclaration),
+            Some(NTermType::InterfaceDeclaration)]);
+        // 'static' without a block is a modifier, not a static initializer
+        assert_eq!(node.children[1].children.len(), 3);
+        // Abstract methods end with ';'
+        let rest = &node.children[4].children[1].children[0].children[2].children[0];
+        assert_eq!(rest.node_type, NodeType::NTerm(NTermType::MethodDeclaratorRest));
+        assert_eq!(rest.children.last().unwrap().val(src), Some(";"));
+    }
+
+    #[test]
+    fn test_parse_sealed_member() {
+        let src = "{ public sealed class A permits B {} non-sealed interface I {} }";
+        let node = parse_class_body(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children[0].children[1].children[0].val(src), Some("sealed"));
+        assert_eq!(node.children[1].children[0].children[0].val(src), Some("non-sealed"));
+        // 'sealed' and 'non-sealed' only modify class and interface declarations
+        for src in &["{ sealed int x; }", "{ non-sealed void m() {} }"] {
+            assert!(parse_class_body(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_err());
+        }
     }
 }
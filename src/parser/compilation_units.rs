@@ -1,7 +1,285 @@
 use super::*;
+use lexer::{ContextualKeyword, TokenType};
+use super::annotation_types::parse_annotation_type_body;
+use super::classes::parse_class_body;
+use super::enums::parse_enum_body;
+use super::interfaces::parse_interface_body;
+use super::modifiers::{is_modifier_or_annot, is_sealed_key, parse_class_modifier};
+use super::records::{is_record_next, parse_record_declaration};
+use super::types::{parse_type, parse_type_list, parse_type_parameters};
 
-#[allow(dead_code)]
-pub fn parse_class_or_interface_declaration(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+/// Returns true if the given token can start a TypeDeclaration.
+pub fn is_type_declaration_start(tok: &Token) -> bool {
+    use lexer::TokenKind::*;
+    is_modifier_or_annot(tok) || matches!(tok.kind, Class | Interface | Enum | Semi)
+        || is_sealed_key(tok) || tok.is_contextual_key(ContextualKeyword::Record)
 }
 
+/// Returns true if '@interface' is next, which starts an annotation type
+/// declaration rather than an annotation.
+pub fn is_annotation_type_next(tokens: &TokenIter) -> bool {
+    let mut clone = tokens.clone();
+    match (clone.next(), clone.next()) {
+        (Some(at), Some(interface)) => at.kind == TokenKind::At
+            && interface.kind == TokenKind::Interface,
+        _ => false,
+    }
+}
+
+/// Returns true if a class or interface declaration is next, possibly after
+/// some modifiers.
+pub fn is_class_or_interface_next(tokens: &TokenIter, src: &str) -> bool {
+    let mut clone = tokens.clone();
+    while let Some(tok) = clone.clone().next() {
+        if (is_modifier_or_annot(&tok) || is_sealed_key(&tok)) && !is_annotation_type_next(&clone) {
+            if parse_class_modifier(&mut clone, src).is_err() {
+                return false;
+            }
+        } else { break }
+    }
+    match clone.clone().next() {
+        Some(tok) => matches!(tok.kind, TokenKind::Class | TokenKind::Interface | TokenKind::Enum)
+            || is_annotation_type_next(&clone) || is_record_next(&clone),
+        None => false,
+    }
+}
+
+pub fn parse_type_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    // A stray ';' is allowed between type declarations
+    let child = match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Semi => term(tokens.next().unwrap()),
+        _ => parse_class_or_interface_declaration(tokens, src)?,
+    };
+    Ok(nterm(NTermType::TypeDeclaration, vec![child]))
+}
+
+pub fn parse_class_or_interface_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        if (is_modifier_or_annot(&tok) || is_sealed_key(&tok)) && !is_annotation_type_next(tokens) {
+            children.push(parse_class_modifier(tokens, src)?);
+        } else { break }
+    }
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Class || tok.kind == TokenKind::Enum
+            || is_record_next(tokens) => children.push(parse_class_declaration(tokens, src)?),
+        Some(tok) if tok.kind == TokenKind::Interface || tok.kind == TokenKind::At =>
+            children.push(parse_interface_declaration(tokens, src)?),
+        Some(tok) => return Err(ParseErr::Point(
+            "Expected class or interface declaration".to_owned(), tok)),
+        None => return Err(ParseErr::Raw(
+            "Expected class or interface declaration, got EOF".to_owned())),
+    }
+    Ok(nterm(NTermType::ClassOrInterfaceDeclaration, children))
+}
+
+pub fn parse_class_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let child = match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Enum => parse_enum_declaration(tokens, src)?,
+        Some(_) if is_record_next(tokens) => parse_record_declaration(tokens, src)?,
+        _ => parse_normal_class_declaration(tokens, src)?,
+    };
+    Ok(nterm(NTermType::ClassDeclaration, vec![child]))
+}
+
+pub fn parse_normal_class_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![assert_term(tokens, src, TokenKind::Class)?,
+                            assert_term_with_type(tokens, TokenType::Ident)?];
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Lt {
+            children.push(parse_type_parameters(tokens, src)?);
+        }
+    }
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Extends {
+            children.push(term(tokens.next().unwrap()));
+            children.push(parse_type(tokens, src)?);
+        }
+    }
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Implements {
+            children.push(term(tokens.next().unwrap()));
+            children.push(parse_type_list(tokens, src)?);
+        }
+    }
+    if let Some(tok) = tokens.clone().next() {
+        if tok.is_contextual_key(ContextualKeyword::Permits) {
+            children.push(parse_permits(tokens, src)?);
+        }
+    }
+    children.push(parse_class_body(tokens, src)?);
+    Ok(nterm(NTermType::NormalClassDeclaration, children))
+}
+
+/// Parses 'permits TypeList', the subclasses allowed to extend a sealed class
+/// or interface.
+pub fn parse_permits(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let permits = match tokens.next() {
+        Some(tok) if tok.is_contextual_key(ContextualKeyword::Permits) => term(tok),
+        Some(tok) => return Err(ParseErr::Point("Expected 'permits'".to_owned(), tok)),
+        None => return Err(ParseErr::Raw("Expected 'permits', got EOF".to_owned())),
+    };
+    Ok(nterm(NTermType::Permits, vec![permits, parse_type_list(tokens, src)?]))
+}
+
+pub fn parse_enum_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![assert_term(tokens, src, TokenKind::Enum)?,
+                            assert_term_with_type(tokens, TokenType::Ident)?];
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Implements {
+            children.push(term(tokens.next().unwrap()));
+            children.push(parse_type_list(tokens, src)?);
+        }
+    }
+    children.push(parse_enum_body(tokens, src)?);
+    Ok(nterm(NTermType::EnumDeclaration, children))
+}
+
+pub fn parse_interface_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let child = match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::At => parse_annotation_type_declaration(tokens, src)?,
+        _ => parse_normal_interface_declaration(tokens, src)?,
+    };
+    Ok(nterm(NTermType::InterfaceDeclaration, vec![child]))
+}
+
+pub fn parse_normal_interface_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![assert_term(tokens, src, TokenKind::Interface)?,
+                            assert_term_with_type(tokens, TokenType::Ident)?];
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Lt {
+            children.push(parse_type_parameters(tokens, src)?);
+        }
+    }
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Extends {
+            children.push(term(tokens.next().unwrap()));
+            children.push(parse_type_list(tokens, src)?);
+        }
+    }
+    if let Some(tok) = tokens.clone().next() {
+        if tok.is_contextual_key(ContextualKeyword::Permits) {
+            children.push(parse_permits(tokens, src)?);
+        }
+    }
+    children.push(parse_interface_body(tokens, src)?);
+    Ok(nterm(NTermType::NormalInterfaceDeclaration, children))
+}
+
+pub fn parse_annotation_type_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    Ok(nterm(NTermType::AnnotationTypeDeclaration, vec![
+        assert_term(tokens, src, TokenKind::At)?,
+        assert_term(tokens, src, TokenKind::Interface)?,
+        assert_term_with_type(tokens, TokenType::Ident)?,
+        parse_annotation_type_body(tokens, src)?]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    fn nterm_type(node: &Node) -> Option<NTermType> {
+        match node.node_type {
+            NodeType::NTerm(ref t) => Some(t.clone()),
+            NodeType::Term(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_class_declaration() {
+        let src = "@Deprecated public final class Foo<K, V extends Comparable<V> & Cloneable>
+                   extends Bar<K> implements Baz, java.io.Serializable { static {} ; }";
+        let node = parse_class_or_interface_declaration(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 4);
+        let class = &node.children[3].children[0];
+        assert_eq!(nterm_type(class), Some(NTermType::NormalClassDeclaration));
+        let types: Vec<_> = class.children.iter().map(nterm_type).collect();
+        assert_eq!(types, vec![None, None, Some(NTermType::TypeParameters), None,
+                               Some(NTermType::Type), None, Some(NTermType::TypeList),
+                               Some(NTermType::ClassBody)]);
+        assert_eq!(class.children[1].val(src), Some("Foo"));
+        assert_eq!(class.children[6].children.len(), 2);
+        assert_eq!(class.children[7].children.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_interface_declaration() {
+        let src = "interface Foo<T> extends Bar<T>, Baz { int A = 1; void foo(); }";
+        let node = parse_class_or_interface_declaration(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let interface = &node.children[0].children[0];
+        assert_eq!(nterm_type(interface), Some(NTermType::NormalInterfaceDeclaration));
+        let types: Vec<_> = interface.children.iter().map(nterm_type).collect();
+        assert_eq!(types, vec![None, None, Some(NTermType::TypeParameters), None,
+                               Some(NTermType::TypeList), Some(NTermType::InterfaceBody)]);
+        assert_eq!(interface.children[5].children.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_enum_declaration() {
+        let src = "public enum Foo implements Bar, Baz { A, B; int a; }";
+        let node = parse_class_or_interface_declaration(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let enum_decl = &node.children[1].children[0];
+        assert_eq!(nterm_type(enum_decl), Some(NTermType::EnumDeclaration));
+        let types: Vec<_> = enum_decl.children.iter().map(nterm_type).collect();
+        assert_eq!(types, vec![None, None, None, Some(NTermType::TypeList),
+                               Some(NTermType::EnumBody)]);
+    }
+
+    #[test]
+    fn test_parse_annotation_type_declaration() {
+        let src = "@Retention(RetentionPolicy.RUNTIME) public @interface Foo { int a() default 1; }";
+        let node = parse_class_or_interface_declaration(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 3);
+        let annotation_type = &node.children[2].children[0];
+        assert_eq!(nterm_type(annotation_type), Some(NTermType::AnnotationTypeDeclaration));
+        assert_eq!(annotation_type.children[2].val(src), Some("Foo"));
+        assert_eq!(nterm_type(&annotation_type.children[3]), Some(NTermType::AnnotationTypeBody));
+    }
+
+    #[test]
+    fn test_parse_sealed_declarations() {
+        let src = "public sealed class Shape extends Base implements I permits Circle, a.Square {}";
+        let node = parse_class_or_interface_declaration(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 3);
+        assert_eq!(node.children[1].children[0].val(src), Some("sealed"));
+        let class = &node.children[2].children[0];
+        let permits = &class.children[6];
+        assert_eq!(nterm_type(permits), Some(NTermType::Permits));
+        assert_eq!(permits.children[1].children.len(), 2);
+
+        let src = "sealed interface A<T> extends B permits C, D {}";
+        let node = parse_class_or_interface_declaration(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let interface = &node.children[1].children[0];
+        assert_eq!(nterm_type(&interface.children[5]), Some(NTermType::Permits));
+
+        let src = "non-sealed class C extends Shape {}";
+        let node = parse_class_or_interface_declaration(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children[0].children[0].val(src), Some("non-sealed"));
+
+        let src = "sealed int x;";
+        assert!(parse_class_or_interface_declaration(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).is_err());
+    }
+
+    #[test]
+    fn test_is_class_or_interface_next() {
+        let next = |src| is_class_or_interface_next(&TokenIter::new(&lex(src, "").unwrap()), src);
+        assert!(next("@Foo(1) static final class A {}"));
+        assert!(next("public @interface A {}"));
+        assert!(!next("final int a = 1;"));
+        assert!(!next("@Foo String s;"));
+        assert!(next("final record A(int a) {}"));
+        assert!(!next("record.foo();"));
+        assert!(next("sealed interface A permits B {}"));
+        assert!(!next("sealed = 1;"));
+        assert!(!next("sealed int x;"));
+    }
+}
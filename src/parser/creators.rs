@@ -14,24 +14,24 @@ use super::types::{is_basic_type,
 #[allow(dead_code)]
 pub fn parse_created_name(tokens: &mut TokenIter, src: &str) -> ParseRes {
     match tokens.clone().next() {
-        Some(tok) if is_basic_type(tok.val(src)) || tok.token_type == TokenType::Ident => (),
-        Some(tok) => return Err(ParseErr::Point("Expected type name".to_owned(), *tok)),
+        Some(tok) if is_basic_type(tok.kind) || tok.token_type == TokenType::Ident => (),
+        Some(tok) => return Err(ParseErr::Point("Expected type name".to_owned(), tok)),
         None => return Err(ParseErr::Raw("Expected type name, got EOF".to_owned())),
     }
 
-    let mut children = vec![term(*tokens.next().unwrap())];
+    let mut children = vec![term(tokens.next().unwrap())];
 
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "<" =>
+        Some(tok) if tok.kind == TokenKind::Lt =>
             children.push(parse_type_arguments_or_diamond(tokens, src)?),
         _ => ()
     }
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "." {
+        if tok.kind == TokenKind::Dot {
             tokens.next().unwrap(); // consume '.'
             children.push(assert_term_with_type(tokens, TokenType::Ident)?);
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "<" =>
+                Some(tok) if tok.kind == TokenKind::Lt =>
                     children.push(parse_type_arguments_or_diamond(tokens, src)?),
                 _ => (),
             }
@@ -44,7 +44,7 @@ pub fn parse_created_name(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_class_creator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_arguments(tokens, src)?];
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "{" => children.push(parse_class_body(tokens, src)?),
+        Some(tok) if tok.kind == TokenKind::LBrace => children.push(parse_class_body(tokens, src)?),
         _ => ()
     }
     Ok(nterm(NTermType::ClassCreatorRest, children))
@@ -52,32 +52,32 @@ pub fn parse_class_creator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
 
 #[allow(dead_code)]
 pub fn parse_array_creator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    let mut children = vec![assert_term(tokens, src, "[")?];
+    let mut children = vec![assert_term(tokens, src, TokenKind::LBracket)?];
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "]" => {
-            children.push(term(*tokens.next().unwrap()));
+        Some(tok) if tok.kind == TokenKind::RBracket => {
+            children.push(term(tokens.next().unwrap()));
             while let Some(tok) = tokens.clone().next() {
-                if tok.val(src) == "[" {
-                    children.push(term(*tokens.next().unwrap()));
-                    children.push(assert_term(tokens, src, "]")?);
+                if tok.kind == TokenKind::LBracket {
+                    children.push(term(tokens.next().unwrap()));
+                    children.push(assert_term(tokens, src, TokenKind::RBracket)?);
                 } else { break }
             }
             children.push(parse_array_initializer(tokens, src)?);
         }
         _ => {
             children.push(parse_expression(tokens, src)?);
-            children.push(assert_term(tokens, src, "]")?);
+            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
             while let Some(tok) = tokens.clone().next() {
-                if tok.val(src) == "[" {
-                    children.push(term(*tokens.next().unwrap()));
+                if tok.kind == TokenKind::LBracket {
+                    children.push(term(tokens.next().unwrap()));
                     children.push(parse_expression(tokens, src)?);
-                    children.push(assert_term(tokens, src, "]")?);
+                    children.push(assert_term(tokens, src, TokenKind::RBracket)?);
                 } else { break }
             }
             while let Some(tok) = tokens.clone().next() {
-                if tok.val(src) == "[" {
-                    children.push(term(*tokens.next().unwrap()));
-                    children.push(assert_term(tokens, src, "]")?);
+                if tok.kind == TokenKind::LBracket {
+                    children.push(term(tokens.next().unwrap()));
+                    children.push(assert_term(tokens, src, TokenKind::RBracket)?);
                 } else { break }
             }
         }
@@ -88,36 +88,36 @@ pub fn parse_array_creator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_identifier_suffix(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "[" => {
-            let mut children = vec![term(*tokens.next().unwrap())];
+        Some(tok) if tok.kind == TokenKind::LBracket => {
+            let mut children = vec![term(tokens.next().unwrap())];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "." || tok.val(src) == "[" => {
+                Some(tok) if tok.kind == TokenKind::Dot || tok.kind == TokenKind::LBracket => {
                     while let Some(tok) = tokens.clone().next() {
-                        if tok.val(src) == "[" {
-                            children.push(term(*tokens.next().unwrap()));
-                            children.push(assert_term(tokens, src, "]")?);
+                        if tok.kind == TokenKind::LBracket {
+                            children.push(term(tokens.next().unwrap()));
+                            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
                         } else { break }
                     }
-                    children.push(assert_term(tokens, src, ".")?);
-                    children.push(assert_term(tokens, src, "class")?);
+                    children.push(assert_term(tokens, src, TokenKind::Dot)?);
+                    children.push(assert_term(tokens, src, TokenKind::Class)?);
                 }
                 _ => children.push(parse_expression(tokens, src)?)
             }
             children
         }
-        Some(tok) if tok.val(src) == "." => {
-            let mut children = vec![term(*tokens.next().unwrap())];
+        Some(tok) if tok.kind == TokenKind::Dot => {
+            let mut children = vec![term(tokens.next().unwrap())];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "class" || tok.val(src) == "this" =>
-                    children.push(term(*tokens.next().unwrap())),
-                Some(tok) if tok.val(src) == "super" => {
-                    children.push(term(*tokens.next().unwrap()));
+                Some(tok) if tok.kind == TokenKind::Class || tok.kind == TokenKind::This =>
+                    children.push(term(tokens.next().unwrap())),
+                Some(tok) if tok.kind == TokenKind::Super => {
+                    children.push(term(tokens.next().unwrap()));
                     children.push(parse_arguments(tokens, src)?);
                 }
-                Some(tok) if tok.val(src) == "new" => {
-                    children.push(term(*tokens.next().unwrap()));
+                Some(tok) if tok.kind == TokenKind::New => {
+                    children.push(term(tokens.next().unwrap()));
                     match tokens.clone().next() {
-                        Some(tok) if tok.val(src) == "<" =>
+                        Some(tok) if tok.kind == TokenKind::Lt =>
                             children.push(parse_non_wildcard_type_arguments(tokens, src)?),
                         _ => ()
                     }
@@ -144,7 +144,7 @@ pub fn parse_explicit_generic_invocation(tokens: &mut TokenIter, src: &str) -> P
 pub fn parse_inner_creator(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![assert_term_with_type(tokens, TokenType::Ident)?];
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "<" => {
+        Some(tok) if tok.kind == TokenKind::Lt => {
             children.push(parse_non_wildcard_type_arguments_or_diamond(tokens, src)?);
         }
         _ => ()
@@ -156,28 +156,28 @@ pub fn parse_inner_creator(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_selector(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "." => {
-            let mut children = vec![term(*tokens.next().unwrap())];
+        Some(tok) if tok.kind == TokenKind::Dot => {
+            let mut children = vec![term(tokens.next().unwrap())];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "this" =>
-                    children.push(term(*tokens.next().unwrap())),
-                Some(tok) if tok.val(src) == "super" => {
-                    children.push(term(*tokens.next().unwrap()));
+                Some(tok) if tok.kind == TokenKind::This =>
+                    children.push(term(tokens.next().unwrap())),
+                Some(tok) if tok.kind == TokenKind::Super => {
+                    children.push(term(tokens.next().unwrap()));
                     children.push(parse_super_suffix(tokens, src)?);
                 }
-                Some(tok) if tok.val(src) == "new" => {
-                    children.push(term(*tokens.next().unwrap()));
+                Some(tok) if tok.kind == TokenKind::New => {
+                    children.push(term(tokens.next().unwrap()));
                     match tokens.clone().next() {
-                        Some(tok) if tok.val(src) == "<" =>
+                        Some(tok) if tok.kind == TokenKind::Lt =>
                             children.push(parse_non_wildcard_type_arguments(tokens, src)?),
                         _ => ()
                     }
                     children.push(parse_inner_creator(tokens, src)?);
                 }
                 Some(tok) if tok.token_type == TokenType::Ident => {
-                    children.push(term(*tokens.next().unwrap()));
+                    children.push(term(tokens.next().unwrap()));
                     match tokens.clone().next() {
-                        Some(tok) if tok.val(src) == "(" =>
+                        Some(tok) if tok.kind == TokenKind::LParen =>
                             children.push(parse_arguments(tokens, src)?),
                         _ => ()
                     }
@@ -187,9 +187,9 @@ pub fn parse_selector(tokens: &mut TokenIter, src: &str) -> ParseRes {
             children
         },
         _ => vec![
-            term(*tokens.next().unwrap()),
+            term(tokens.next().unwrap()),
             parse_expression(tokens, src)?,
-            assert_term(tokens, src, "]")?
+            assert_term(tokens, src, TokenKind::RBracket)?
         ]
     };
     Ok(nterm(NTermType::Selector, children))
@@ -198,14 +198,14 @@ pub fn parse_selector(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_creator(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "<" => vec![
+        Some(tok) if tok.kind == TokenKind::Lt => vec![
             parse_non_wildcard_type_arguments(tokens, src)?,
             parse_created_name(tokens, src)?,
             parse_class_creator_rest(tokens, src)?],
         _ => vec![
             parse_created_name(tokens, src)?,
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "[" => parse_array_creator_rest(tokens, src)?,
+                Some(tok) if tok.kind == TokenKind::LBracket => parse_array_creator_rest(tokens, src)?,
                 _ => parse_class_creator_rest(tokens, src)?,
             }]
     };
@@ -220,12 +220,12 @@ mod tests {
     #[test]
     pub fn test_parse_creator() {
         let src = "Foo(x, y, 1 + 2)";
-        let node = parse_creator(&mut lex(src, "").unwrap().iter(), src);
+        let node = parse_creator(&mut TokenIter::new(&lex(src, "").unwrap()), src);
         let node = node.unwrap();
         assert_eq!(node.children.len(), 2);
 
         let src = "double[x * 32]";
-        let node = parse_creator(&mut lex(src, "").unwrap().iter(), src);
+        let node = parse_creator(&mut TokenIter::new(&lex(src, "").unwrap()), src);
         let node = node.unwrap();
         assert_eq!(node.children.len(), 2);
     }
@@ -0,0 +1,119 @@
+//! Parsing for enum bodies
+
+use super::*;
+use lexer::TokenType;
+use super::annotations::parse_annotations;
+use super::atoms::parse_arguments;
+use super::classes::{parse_class_body, parse_class_body_declaration};
+
+#[allow(dead_code)]
+pub fn parse_enum_body(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    assert_term(tokens, src, TokenKind::LBrace)?;
+    let mut children = Vec::new();
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Ident || tok.kind == TokenKind::At =>
+            children.push(parse_enum_constants(tokens, src)?),
+        _ => (),
+    }
+    // The constants may have a trailing ','
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Comma {
+            tokens.next(); // Skip ','
+        }
+    }
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Semi {
+            children.push(parse_enum_body_declarations(tokens, src)?);
+        }
+    }
+    assert_term(tokens, src, TokenKind::RBrace)?;
+    Ok(nterm(NTermType::EnumBody, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_enum_constants(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_enum_constant(tokens, src)?];
+    loop {
+        // A ',' not followed by another constant is the trailing comma, which
+        // is left for the EnumBody
+        let mut clone = tokens.clone();
+        match (clone.next(), clone.next()) {
+            (Some(comma), Some(tok)) if comma.kind == TokenKind::Comma
+                && (tok.kind == TokenKind::Ident || tok.kind == TokenKind::At) => {
+                tokens.next(); // Skip ','
+                children.push(parse_enum_constant(tokens, src)?);
+            }
+            _ => break,
+        }
+    }
+    Ok(nterm(NTermType::EnumConstants, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_enum_constant(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::At {
+            children.push(parse_annotations(tokens, src)?);
+        }
+    }
+    children.push(assert_term_with_type(tokens, TokenType::Ident)?);
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::LParen {
+            children.push(parse_arguments(tokens, src)?);
+        }
+    }
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::LBrace {
+            children.push(parse_class_body(tokens, src)?);
+        }
+    }
+    Ok(nterm(NTermType::EnumConstant, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_enum_body_declarations(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    assert_term(tokens, src, TokenKind::Semi)?;
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        match tok.kind {
+            TokenKind::RBrace => break,
+            _ => children.push(parse_class_body_declaration(tokens, src)?),
+        }
+    }
+    Ok(nterm(NTermType::EnumBodyDeclarations, children))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    #[test]
+    fn test_parse_enum_body() {
+        let src = "{
+            @Deprecated A, B(1, \"b\"), C { int foo() { return 1; } },;
+            private final int a;
+            Foo() { this(0); }
+            Foo(int a) { this.a = a; }
+        }";
+        let node = parse_enum_body(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 2);
+        let constants = &node.children[0];
+        assert_eq!(constants.children.len(), 3);
+        assert_eq!(constants.children[0].children[0].node_type,
+                   NodeType::NTerm(NTermType::Annotations));
+        assert_eq!(constants.children[1].children[1].node_type,
+                   NodeType::NTerm(NTermType::Arguments));
+        assert_eq!(constants.children[2].children[1].node_type,
+                   NodeType::NTerm(NTermType::ClassBody));
+        assert_eq!(node.children[1].children.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_empty_enum_body() {
+        for src in &["{}", "{,}", "{;}", "{ A, }"] {
+            assert!(parse_enum_body(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok());
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use lexer::Token;
+use lexer::{Token, SourceMap};
 
 #[derive(Eq, PartialEq, Debug, Clone)]
 pub enum ParseErr {
@@ -14,16 +14,8 @@ impl ParseErr {
         match *self {
             ParseErr::Raw(ref s) => println!("{} - {}", filename, s),
             ParseErr::Point(ref s, ref t) => {
-                // find the line num
-                let mut line_num = 1;
-                for (ix, c) in src.char_indices() {
-                    if ix == t.start.0 { break; }
-                    if c == '\n' {
-                        line_num += 1;
-                    }
-                }
-
-                println!("{} - {}:{}", s, filename, line_num);
+                let lc = SourceMap::new(src).line_col(t.start);
+                println!("{} - {}:{}:{}", s, filename, lc.line + 1, lc.col_utf16 + 1);
             }
         }
     }
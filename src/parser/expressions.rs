@@ -3,63 +3,67 @@ use super::atoms::parse_primary;
 use lexer::TokenType;
 use super::types::{parse_type};
 use super::creators::parse_selector;
+use super::switches::parse_switch_expression;
+use super::lambdas::{is_lambda_next, parse_lambda_expression};
+use super::method_references::{is_super_method_reference_next, is_type_method_reference_next,
+                               parse_method_reference};
 
-fn is_prefix_op(s: &str) -> bool {
-    s == "++" || s == "--" || s == "!" || s == "~" || s == "+" || s == "-"
+fn is_prefix_op(kind: TokenKind) -> bool {
+    use lexer::TokenKind::*;
+    matches!(kind, PlusPlus | MinusMinus | Bang | Tilde | Plus | Minus)
 }
 
-fn is_postfix_op(s: &str) -> bool {
-    s == "++" || s == "--"
+fn is_postfix_op(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::PlusPlus | TokenKind::MinusMinus)
 }
 
-fn is_infix_op(s: &str) -> bool {
-        s == "||" || s == "&&" || s == "|" || s == "^" ||
-        s == "&" || s == "==" || s == "!=" || s == "<" ||
-        s == ">" || s == "<=" || s == ">=" || s == "<<" ||
-        s == ">>" || s == ">>>" || s == "+" || s == "-" ||
-        s == "*" || s == "/" || s == "%"
+fn is_infix_op(kind: TokenKind) -> bool {
+    use lexer::TokenKind::*;
+    matches!(kind, PipePipe | AmpAmp | Pipe | Caret | Amp | EqEq | BangEq | Lt | Gt | LtEq
+             | GtEq | LtLt | GtGt | GtGtGt | Plus | Minus | Star | Slash | Percent)
 }
 
-fn is_assignment_op(s: &str) -> bool {
-    s == "=" || s == "+=" || s == "-=" || s == "*=" || s == "/=" || s == "&=" ||
-        s == "|=" || s == "^=" || s == "%=" || s == "<<=" || s == ">>=" || s == ">>>="
+pub fn is_assignment_op(kind: TokenKind) -> bool {
+    use lexer::TokenKind::*;
+    matches!(kind, Eq | PlusEq | MinusEq | StarEq | SlashEq | AmpEq | PipeEq | CaretEq
+             | PercentEq | LtLtEq | GtGtEq | GtGtGtEq)
 }
 
 #[allow(dead_code)]
-pub fn parse_prefix_op(tokens: &mut TokenIter, src: &str) -> ParseRes {
+pub fn parse_prefix_op(tokens: &mut TokenIter, _src: &str) -> ParseRes {
     match tokens.next() {
-        Some(tok) if is_prefix_op(tok.val(src)) => Ok(nterm(NTermType::PrefixOp, vec![term(*tok)])),
-        Some(tok) => Err(ParseErr::Point("Expected prefix operator".to_owned(), *tok)),
+        Some(tok) if is_prefix_op(tok.kind) => Ok(nterm(NTermType::PrefixOp, vec![term(tok)])),
+        Some(tok) => Err(ParseErr::Point("Expected prefix operator".to_owned(), tok)),
         None => Err(ParseErr::Raw("Expected prefix operator, got EOF".to_owned()))
     }
 }
 
 #[allow(dead_code)]
-pub fn parse_postfix_op(tokens: &mut TokenIter, src: &str) -> ParseRes {
+pub fn parse_postfix_op(tokens: &mut TokenIter, _src: &str) -> ParseRes {
     match tokens.next() {
-        Some(tok) if is_postfix_op(tok.val(src))
-            => Ok(nterm(NTermType::PostfixOp, vec![term(*tok)])),
-        Some(tok) => Err(ParseErr::Point("Expected postfix operator".to_owned(), *tok)),
+        Some(tok) if is_postfix_op(tok.kind)
+            => Ok(nterm(NTermType::PostfixOp, vec![term(tok)])),
+        Some(tok) => Err(ParseErr::Point("Expected postfix operator".to_owned(), tok)),
         None => Err(ParseErr::Raw("Expected postfix operator, got EOF".to_owned()))
     }
 }
 
 #[allow(dead_code)]
-pub fn parse_infix_op(tokens: &mut TokenIter, src: &str) -> ParseRes {
+pub fn parse_infix_op(tokens: &mut TokenIter, _src: &str) -> ParseRes {
     match tokens.next() {
-        Some(tok) if is_infix_op(tok.val(src))
-            => Ok(nterm(NTermType::InfixOp, vec![term(*tok)])),
-        Some(tok) => Err(ParseErr::Point("Expected operator".to_owned(), *tok)),
+        Some(tok) if is_infix_op(tok.kind)
+            => Ok(nterm(NTermType::InfixOp, vec![term(tok)])),
+        Some(tok) => Err(ParseErr::Point("Expected operator".to_owned(), tok)),
         None => Err(ParseErr::Raw("Expected operator, got EOF".to_owned()))
     }
 }
 
 #[allow(dead_code)]
-pub fn parse_assignment_op(tokens: &mut TokenIter, src: &str) -> ParseRes {
+pub fn parse_assignment_op(tokens: &mut TokenIter, _src: &str) -> ParseRes {
     match tokens.next() {
-        Some(tok) if is_assignment_op(tok.val(src))
-            => Ok(nterm(NTermType::AssignmentOperator, vec![term(*tok)])),
-        Some(tok) => Err(ParseErr::Point("Expected assignment operator".to_owned(), *tok)),
+        Some(tok) if is_assignment_op(tok.kind)
+            => Ok(nterm(NTermType::AssignmentOperator, vec![term(tok)])),
+        Some(tok) => Err(ParseErr::Point("Expected assignment operator".to_owned(), tok)),
         None => Err(ParseErr::Raw("Expected assignment operator, got EOF".to_owned()))
     }
 }
@@ -67,7 +71,7 @@ pub fn parse_assignment_op(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_expression(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_expression1(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if is_assignment_op(tok.val(src)) {
+        if is_assignment_op(tok.kind) {
             children.push(parse_assignment_op(tokens, src)?);
             children.push(parse_expression1(tokens, src)?);
         } else { break }
@@ -78,7 +82,7 @@ pub fn parse_expression(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_expression1(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_expression2(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "?" {
+        if tok.kind == TokenKind::Question {
             children.push(parse_expression1_rest(tokens, src)?)
         } else { break }
     }
@@ -88,9 +92,9 @@ pub fn parse_expression1(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_expression1_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
     Ok(nterm(NTermType::Expression1Rest, vec![
-        assert_term(tokens, src, "?")?,
+        assert_term(tokens, src, TokenKind::Question)?,
         parse_expression(tokens, src)?,
-        assert_term(tokens, src, ":")?,
+        assert_term(tokens, src, TokenKind::Colon)?,
         parse_expression1(tokens, src)?]))
 }
 
@@ -99,8 +103,8 @@ pub fn parse_expression1_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_expression2(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_expression3(tokens, src)?];
     match tokens.clone().next() {
-        Some(tok) if is_infix_op(tok.val(src)) ||
-            tok.val(src) == "instanceof" =>
+        Some(tok) if is_infix_op(tok.kind) ||
+            tok.kind == TokenKind::Instanceof =>
             children.push(parse_expression2_rest(tokens, src)?),
         _ => ()
     }
@@ -110,13 +114,13 @@ pub fn parse_expression2(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_expression2_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "instanceof" => vec![
-            term(*tokens.next().unwrap()),
+        Some(tok) if tok.kind == TokenKind::Instanceof => vec![
+            term(tokens.next().unwrap()),
             parse_type(tokens, src)?],
         _ => {
             let mut children = Vec::new();
             while let Some(tok) = tokens.clone().next() {
-                if is_infix_op(tok.val(src)) {
+                if is_infix_op(tok.kind) {
                     children.push(parse_infix_op(tokens, src)?);
                     children.push(parse_expression3(tokens, src)?);
                 } else { break }
@@ -130,7 +134,17 @@ pub fn parse_expression2_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_expression3(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if is_prefix_op(tok.val(src)) => vec![
+        Some(_) if is_lambda_next(tokens, src) => vec![parse_lambda_expression(tokens, src)?],
+        Some(tok) if tok.kind == TokenKind::Switch => vec![parse_switch_expression(tokens, src)?],
+        Some(_) if is_super_method_reference_next(tokens) => {
+            let qualifier = vec![term(tokens.next().unwrap())]; // "super"
+            vec![parse_method_reference(tokens, src, qualifier)?]
+        }
+        Some(_) if is_type_method_reference_next(tokens, src) => {
+            let qualifier = vec![parse_type(tokens, src)?];
+            vec![parse_method_reference(tokens, src, qualifier)?]
+        }
+        Some(tok) if is_prefix_op(tok.kind) => vec![
                 parse_prefix_op(tokens, src)?,
                 parse_expression3(tokens, src)?],
         // Small deviation from the grammar here - the grammar appears to be
@@ -140,14 +154,14 @@ pub fn parse_expression3(tokens: &mut TokenIter, src: &str) -> ParseRes {
         // We simply search until the matching rparen, and check the next token.
         // If the next token is a ( or Ident, it's a cast - otherwise, it's an
         // expression.
-        Some(tok) if tok.val(src) == "(" => {
+        Some(tok) if tok.kind == TokenKind::LParen => {
             // Find the matching ')'
             let mut level = -1;
             let mut clone = tokens.clone();
             for t in clone.by_ref() {
-                if t.val(src) == "(" {
+                if t.kind == TokenKind::LParen {
                     level += 1;
-                } else if t.val(src) == ")" {
+                } else if t.kind == TokenKind::RParen {
                     level -= 1;
                     if level < 0 {
                         break;
@@ -159,34 +173,38 @@ pub fn parse_expression3(tokens: &mut TokenIter, src: &str) -> ParseRes {
                                          .to_owned()));
             }
             // Now the next token in 'clone' is the token just after the last rparen.
-            let children = match clone.next() {
+            match clone.next() {
                 // Type cast
-                Some(tok) if tok.val(src) == "("
+                Some(tok) if tok.kind == TokenKind::LParen
                     || tok.token_type == TokenType::Ident
                     || tok.is_literal() => {
-                    vec![term(*tokens.next().unwrap()), // (
+                    vec![term(tokens.next().unwrap()), // (
                          parse_type(tokens, src)?,
-                         assert_term(tokens, src, ")")?,
+                         assert_term(tokens, src, TokenKind::RParen)?,
                          parse_expression3(tokens, src)?]
                 }
                 // Expr
                 _ => {
-                    vec![term(*tokens.next().unwrap()), // (
+                    vec![term(tokens.next().unwrap()), // (
                          parse_expression(tokens, src)?,
-                         assert_term(tokens, src, ")")?]
+                         assert_term(tokens, src, TokenKind::RParen)?]
                 }
-            };
-            children
+            }
         }
         _ => {
             let mut children = vec![parse_primary(tokens, src)?];
             while let Some(tok) = tokens.clone().next() {
-                if tok.val(src) == "." {
+                if tok.kind == TokenKind::Dot {
                     children.push(parse_selector(tokens, src)?);
                 } else { break }
             }
+            if let Some(tok) = tokens.clone().next() {
+                if tok.kind == TokenKind::ColonColon {
+                    children = vec![parse_method_reference(tokens, src, children)?];
+                }
+            }
             while let Some(tok) = tokens.clone().next() {
-                if is_postfix_op(tok.val(src)) {
+                if is_postfix_op(tok.kind) {
                     children.push(parse_postfix_op(tokens, src)?);
                 } else { break }
             }
@@ -205,7 +223,7 @@ mod tests {
     fn test_parse_prefix_op() {
         let src = ["++", "--", "!", "~", "+", "-"];
         assert!(src.iter().all(|src| {
-            parse_prefix_op(&mut lex(src, "").unwrap().iter(), src).is_ok()
+            parse_prefix_op(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok()
         }));
     }
 
@@ -213,7 +231,7 @@ mod tests {
     fn test_parse_postfix_op() {
         let src = ["++", "--"];
         assert!(src.iter().all(|src| {
-            parse_postfix_op(&mut lex(src, "").unwrap().iter(), src).is_ok()
+            parse_postfix_op(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok()
         }));
     }
 
@@ -222,7 +240,7 @@ mod tests {
         let src = ["||", "&&", "|", "^", "&", "==", "!=", "<", ">", "<=", ">=",
                    "<<", ">>", ">>>", "+", "-", "*", "/", "%"];
         assert!(src.iter().all(|src| {
-            parse_infix_op(&mut lex(src, "").unwrap().iter(), src).is_ok()
+            parse_infix_op(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok()
         }));
     }
 
@@ -231,58 +249,79 @@ mod tests {
         let src = ["=", "+=", "-=", "*=", "/=", "&=", "|=", "^=",
                    "%=", "<<=", ">>=", ">>>="];
         assert!(src.iter().all(|src| {
-            parse_assignment_op(&mut lex(src, "").unwrap().iter(), src).is_ok()
+            parse_assignment_op(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok()
         }));
     }
 
     #[test]
     fn test_parse_full_expression() {
         let src = "x = y + (float)45 - ((float)i++ - 54.0)";
-        let node = parse_expression(&mut lex(src, "").unwrap().iter(), src);
+        let node = parse_expression(&mut TokenIter::new(&lex(src, "").unwrap()), src);
         let node = node.unwrap();
         assert_eq!(node.children.len(), 3);
     }
 
+    #[test]
+    fn test_parse_non_sealed_subtraction() {
+        // Outside of modifiers, 'non-sealed' is 'non' minus 'sealed'
+        let src = "a = non-sealed";
+        let node = parse_expression(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let rest = &node.children[2].children[0].children[1];
+        assert_eq!(rest.node_type, NodeType::NTerm(NTermType::Expression2Rest));
+        assert_eq!(rest.children[0].children[0].val(src), Some("-"));
+        assert_eq!(rest.children[1].span().map(|(start, end)| &src[start.0..end.0]),
+                   Some("sealed"));
+    }
+
     #[test]
     fn test_parse_expression2() {
         let src = "(float)x + (float)y + 2.0";
-        let node = parse_expression2(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression2(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
 
         let src = "4 + 7 + 234";
-        let node = parse_expression2(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression2(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
 
         let src = "x";
-        let node = parse_expression2(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression2(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
 
         let src = "(Foo)x";
-        let node = parse_expression2(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression2(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
     }
 
     #[test]
     fn test_parse_expression2_rest() {
         let src = "+ foo + bar";
-        let node = parse_expression2_rest(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression2_rest(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 4);
 
         let src = "instanceof Foo";
-        let node = parse_expression2_rest(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression2_rest(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
     }
 
     #[test]
     fn test_parse_expression3() {
         let src = "i++";
-        let node = parse_expression3(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression3(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
         let src = "++i";
-        let node = parse_expression3(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression3(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
         let src = "-i";
-        let node = parse_expression3(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_expression3(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
     }
+
+    #[test]
+    fn test_parse_lambda_argument() {
+        let src = "list.forEach(x -> foo(x))";
+        assert!(parse_expression(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok());
+        let src = "f = (a, b) -> a + b";
+        let node = parse_expression(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 3);
+    }
 }
@@ -12,25 +12,25 @@ fn parse_for_var_control(tokens: &mut TokenIter, src: &str) -> ParseRes {
     // 'forvarcontrolrest', just the one 'forvarcontrol' and assuming that the
     // ': Expression' production is chosen.
     let mut children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "@" || tok.val(src) == "final" =>
+        Some(tok) if tok.kind == TokenKind::At || tok.kind == TokenKind::Final =>
             vec![parse_variable_modifier(tokens, src)?],
         _ => vec![],
     };
     children.push(parse_type(tokens, src)?);
     children.push(parse_variable_declarator_id(tokens, src)?);
-    children.push(assert_term(tokens, src, ":")?);
+    children.push(assert_term(tokens, src, TokenKind::Colon)?);
     children.push(parse_expression(tokens, src)?);
     Ok(nterm(NTermType::ForVarControl, children))
 }
 
 fn parse_for_init(tokens: &mut TokenIter, src: &str) -> ParseRes {
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == ";" => return Ok(nterm(NTermType::ForInit, vec![])),
+        Some(tok) if tok.kind == TokenKind::Semi => return Ok(nterm(NTermType::ForInit, vec![])),
         _ => (),
     }
     let mut children = vec![parse_statement_expression(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
+        if tok.kind == TokenKind::Comma {
             tokens.next().unwrap(); // Consume ","
             children.push(parse_statement_expression(tokens, src)?);
         } else { break }
@@ -40,12 +40,12 @@ fn parse_for_init(tokens: &mut TokenIter, src: &str) -> ParseRes {
 
 fn parse_for_update(tokens: &mut TokenIter, src: &str) -> ParseRes {
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == ")" => return Ok(nterm(NTermType::ForUpdate, vec![])),
+        Some(tok) if tok.kind == TokenKind::RParen => return Ok(nterm(NTermType::ForUpdate, vec![])),
         _ => (),
     }
     let mut children = vec![parse_expression(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
+        if tok.kind == TokenKind::Comma {
             tokens.next().unwrap(); // Consume ","
             children.push(parse_expression(tokens, src)?);
         } else { break }
@@ -65,9 +65,9 @@ pub fn parse_for_control(tokens: &mut TokenIter, src: &str) -> ParseRes {
 
     let mut contains_colon = false;
     for t in tokens.clone() {
-        match t.val(src) {
-            ";" | ")" => break,
-            ":" => {
+        match t.kind {
+            TokenKind::Semi | TokenKind::RParen => break,
+            TokenKind::Colon => {
                 contains_colon = true;
                 break;
             }
@@ -80,9 +80,9 @@ pub fn parse_for_control(tokens: &mut TokenIter, src: &str) -> ParseRes {
     } else {
         Ok(nterm(NTermType::ForControl, vec![
             parse_for_init(tokens, src)?,
-            assert_term(tokens, src, ";")?,
+            assert_term(tokens, src, TokenKind::Semi)?,
             parse_expression(tokens, src)?,
-            assert_term(tokens, src, ";")?,
+            assert_term(tokens, src, TokenKind::Semi)?,
             parse_for_update(tokens, src)?,
         ]))
     }
@@ -96,7 +96,7 @@ mod tests {
     #[test]
     fn test_parse_for_var_control() {
         let src = "String s : someStringList";
-        let node = parse_for_control(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_for_control(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         assert_eq!(node.children[0].children.len(), 4);
         match node.children[0].node_type {
@@ -108,7 +108,7 @@ mod tests {
     #[test]
     fn test_parse_for_expr_control() {
         let src = "int ii = 0; ii < someList.len(); ii ++";
-        let node = parse_for_control(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_for_control(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 5);
     }
 }
@@ -5,12 +5,12 @@ use super::variables::parse_variable_modifier;
 
 #[allow(dead_code)]
 pub fn parse_formal_parameters(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    let mut children = vec![assert_term(tokens, src, "(")?];
+    let mut children = vec![assert_term(tokens, src, TokenKind::LParen)?];
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == ")" => (),
+        Some(tok) if tok.kind == TokenKind::RParen => (),
         _ => children.push(parse_formal_parameter_decls(tokens, src)?),
     }
-    children.push(assert_term(tokens, src, ")")?);
+    children.push(assert_term(tokens, src, TokenKind::RParen)?);
     Ok(nterm(NTermType::FormalParameters, children))
 }
 
@@ -18,8 +18,8 @@ pub fn parse_formal_parameters(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_formal_parameter_decls(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = Vec::new();
     while let Some(tok) = tokens.clone().next() {
-        match tok.val(src) {
-            "final" | "@" => children.push(parse_variable_modifier(tokens, src)?),
+        match tok.kind {
+            TokenKind::Final | TokenKind::At => children.push(parse_variable_modifier(tokens, src)?),
             _ => break
         }
     }
@@ -31,13 +31,13 @@ pub fn parse_formal_parameter_decls(tokens: &mut TokenIter, src: &str) -> ParseR
 #[allow(dead_code)]
 pub fn parse_formal_parameter_decls_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let children = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "..." => vec![
-            term(*tokens.next().unwrap()),
+        Some(tok) if tok.kind == TokenKind::Ellipsis => vec![
+            term(tokens.next().unwrap()),
             parse_variable_declarator_id(tokens, src)?],
         _ => {
             let mut children = vec![parse_variable_declarator_id(tokens, src)?];
             while let Some(tok) = tokens.clone().next() {
-                if tok.val(src) == "," {
+                if tok.kind == TokenKind::Comma {
                     tokens.next().unwrap(); // Consume ","
                     children.push(parse_formal_parameter_decls(tokens, src)?);
                 } else { break }
@@ -52,9 +52,9 @@ pub fn parse_formal_parameter_decls_rest(tokens: &mut TokenIter, src: &str) -> P
 pub fn parse_variable_declarator_id(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![assert_term_with_type(tokens, TokenType::Ident)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "[" {
-            children.push(term(*tokens.next().unwrap()));
-            children.push(assert_term(tokens, src, "]")?);
+        if tok.kind == TokenKind::LBracket {
+            children.push(term(tokens.next().unwrap()));
+            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
         } else { break }
     }
     Ok(nterm(NTermType::VariableDeclaratorId, children))
@@ -69,7 +69,7 @@ mod tests {
     #[test]
     fn test_parse_formal_parameters() {
         let src = "(int a, int b, Foo<T> someFoo, char[][] charArray)";
-        let node = parse_formal_parameters(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_formal_parameters(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
     }
 }
@@ -4,15 +4,19 @@ use super::*;
 use lexer::TokenType;
 
 /// A qualified identifier is just a node that contains a list of identifiers.
-/// The '.' separating the idents are stripped.
-pub fn parse_qualified_identifier(tokens: &mut TokenIter, src: &str) -> ParseRes {
+/// The '.' separating the idents are stripped. A '.' which isn't followed by an
+/// identifier, like the one in 'import a.*;', is left for the caller.
+pub fn parse_qualified_identifier(tokens: &mut TokenIter, _src: &str) -> ParseRes {
     let mut children = vec![assert_term_with_type(tokens, TokenType::Ident)?];
-    while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "." {
-            tokens.next(); // Skip '.'
-            children.push(assert_term_with_type(tokens, TokenType::Ident)?);
-        } else {
-            break;
+    loop {
+        let mut clone = tokens.clone();
+        match (clone.next(), clone.next()) {
+            (Some(dot), Some(ident)) if dot.kind == TokenKind::Dot
+                && ident.token_type == TokenType::Ident => {
+                tokens.next(); // Skip '.'
+                children.push(term(tokens.next().unwrap()));
+            }
+            _ => break,
         }
     }
     Ok(nterm(NTermType::QualifiedIdentifier, children))
@@ -22,7 +26,7 @@ pub fn parse_qualified_identifier(tokens: &mut TokenIter, src: &str) -> ParseRes
 pub fn parse_qualified_identifier_list(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_qualified_identifier(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
+        if tok.kind == TokenKind::Comma {
             tokens.next(); // Skip ','
             children.push(parse_qualified_identifier(tokens, src)?);
         } else {
@@ -40,7 +44,7 @@ mod tests {
     #[test]
     fn test_parse_qualified_identifier() {
         let src = "com.tom.project.Foo";
-        let node = parse_qualified_identifier(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_qualified_identifier(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 4);
         assert_eq!(node.children[0].val(src), Some("com"));
         assert_eq!(node.children[1].val(src), Some("tom"));
@@ -51,7 +55,7 @@ mod tests {
     #[test]
     fn test_parse_qualified_identifier_list() {
         let src = "com.tom.project.Foo, com.tom.project.Bar";
-        let node = parse_qualified_identifier_list(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_qualified_identifier_list(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
         assert_eq!(node.children[0].children[0].val(src), Some("com"));
         assert_eq!(node.children[0].children[1].val(src), Some("tom"));
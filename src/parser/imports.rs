@@ -1,14 +1,13 @@
 use super::*;
 use super::util::consume_maybe;
 use super::identifiers::parse_qualified_identifier;
-use lexer::TokenType;
 
 #[allow(dead_code)]
 pub fn parse_import(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![
-        assert_term(tokens, src, "import")?,
+        assert_term(tokens, src, TokenKind::Import)?,
     ];
-    if let Some(tok) = consume_maybe(tokens, src, "static", TokenType::Key) {
+    if let Some(tok) = consume_maybe(tokens, TokenKind::Static) {
         children.push(term(tok));
     }
     children.push(parse_qualified_identifier(tokens, src)?);
@@ -16,19 +15,19 @@ pub fn parse_import(tokens: &mut TokenIter, src: &str) -> ParseRes {
     // Add [.*]
     let mut clone = tokens.clone();
     match clone.next() {
-        Some(tok) if tok.val(src) == "." => match clone.next() {
-            Some(tok) if tok.val(src) == "*" => {
-                children.push(term(*tokens.next().unwrap()));
-                children.push(term(*tokens.next().unwrap()));
+        Some(tok) if tok.kind == TokenKind::Dot => match clone.next() {
+            Some(tok) if tok.kind == TokenKind::Star => {
+                children.push(term(tokens.next().unwrap()));
+                children.push(term(tokens.next().unwrap()));
             }
             _ => (),
         }
         _ => (),
     }
 
-    children.push(assert_term(tokens, src, ";")?);
+    children.push(assert_term(tokens, src, TokenKind::Semi)?);
 
-    Ok(nterm(NTermType::QualifiedIdentifier, children))
+    Ok(nterm(NTermType::ImportDeclaration, children))
 }
 
 #[cfg(test)]
@@ -39,7 +38,7 @@ mod tests {
     #[test]
     fn test_parse_import() {
         let src = "import com.tom.project.Foo;";
-        let node = parse_import(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_import(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children[0].val(src), Some("import"));
         assert_eq!(node.children[2].val(src), Some(";"));
     }
@@ -47,11 +46,20 @@ mod tests {
     #[test]
     fn test_parse_static_import() {
         let src = "import static com.tom.project.Foo;";
-        let node = parse_import(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_import(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 4);
         assert_eq!(node.children[0].val(src), Some("import"));
         assert_eq!(node.children[1].val(src), Some("static"));
         assert_eq!(node.children[3].val(src), Some(";"));
     }
 
+    #[test]
+    fn test_parse_import_on_demand() {
+        let src = "import static com.tom.project.Foo.*;";
+        let node = parse_import(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 6);
+        assert_eq!(node.children[2].children.len(), 4);
+        assert_eq!(node.children[4].val(src), Some("*"));
+    }
+
 }
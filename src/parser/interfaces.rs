@@ -0,0 +1,194 @@
+//! Parsing for interface bodies
+
+use super::*;
+use lexer::TokenType;
+use super::classes::parse_throws_maybe;
+use super::compilation_units::{is_annotation_type_next, is_class_or_interface_next,
+                               parse_class_declaration, parse_interface_declaration};
+use super::formal_parameters::parse_formal_parameters;
+use super::modifiers::{is_modifier_or_annot, is_sealed_key, parse_class_modifier,
+                       parse_modifier};
+use super::records::is_record_next;
+use super::statements::parse_block;
+use super::types::{parse_type, parse_type_parameters};
+use super::variables::parse_variable_initializer;
+
+#[allow(dead_code)]
+pub fn parse_interface_body(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    assert_term(tokens, src, TokenKind::LBrace)?;
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        match tok.kind {
+            TokenKind::RBrace => break,
+            _ => children.push(parse_interface_body_declaration(tokens, src)?),
+        }
+    }
+    assert_term(tokens, src, TokenKind::RBrace)?;
+    Ok(nterm(NTermType::InterfaceBody, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_interface_body_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let children = match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Semi => vec![term(tokens.next().unwrap())],
+        _ => {
+            let mut children = Vec::new();
+            // Parse modifier list. 'default' is only a modifier on interface
+            // methods, elsewhere it's a switch label.
+            while let Some(tok) = tokens.clone().next() {
+                if tok.kind == TokenKind::Default {
+                    children.push(nterm(NTermType::Modifier, vec![term(tokens.next().unwrap())]));
+                } else if is_modifier_or_annot(&tok) && !is_annotation_type_next(tokens) {
+                    children.push(parse_modifier(tokens, src)?);
+                } else if is_sealed_key(&tok) && is_class_or_interface_next(tokens, src) {
+                    children.push(parse_class_modifier(tokens, src)?);
+                } else { break }
+            }
+            children.push(parse_interface_member_decl(tokens, src)?);
+            children
+        }
+    };
+    Ok(nterm(NTermType::InterfaceBodyDeclaration, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_interface_member_decl(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let tok = tokens.clone().next().ok_or(
+        ParseErr::Raw("Unexpected EOF, expected interface member".to_owned()))?;
+    let children = match tok.kind {
+        TokenKind::Class | TokenKind::Enum => vec![parse_class_declaration(tokens, src)?],
+        TokenKind::Ident if is_record_next(tokens) => vec![parse_class_declaration(tokens, src)?],
+        TokenKind::Interface | TokenKind::At => vec![parse_interface_declaration(tokens, src)?],
+        TokenKind::Void => vec![
+            term(tokens.next().unwrap()),
+            assert_term_with_type(tokens, TokenType::Ident)?,
+            parse_void_interface_method_declarator_rest(tokens, src)?],
+        TokenKind::Lt => vec![parse_interface_generic_method_decl(tokens, src)?],
+        _ => vec![parse_interface_method_or_field_decl(tokens, src)?],
+    };
+    Ok(nterm(NTermType::InterfaceMemberDecl, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_interface_method_or_field_decl(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    Ok(nterm(NTermType::InterfaceMethodOrFieldDecl, vec![
+        parse_type(tokens, src)?,
+        assert_term_with_type(tokens, TokenType::Ident)?,
+        parse_interface_method_or_field_rest(tokens, src)?]))
+}
+
+#[allow(dead_code)]
+pub fn parse_interface_method_or_field_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let children = match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::LParen =>
+            vec![parse_interface_method_declarator_rest(tokens, src)?],
+        _ => vec![parse_constant_declarators_rest(tokens, src)?,
+                  assert_term(tokens, src, TokenKind::Semi)?],
+    };
+    Ok(nterm(NTermType::InterfaceMethodOrFieldRest, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_constant_declarators_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_constant_declarator_rest(tokens, src)?];
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Comma {
+            tokens.next(); // Skip ','
+            children.push(parse_constant_declarator(tokens, src)?);
+        } else { break }
+    }
+    Ok(nterm(NTermType::ConstantDeclaratorsRest, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_constant_declarator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::LBracket {
+            children.push(term(tokens.next().unwrap()));
+            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
+        } else { break }
+    }
+    children.push(assert_term(tokens, src, TokenKind::Eq)?);
+    children.push(parse_variable_initializer(tokens, src)?);
+    Ok(nterm(NTermType::ConstantDeclaratorRest, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_constant_declarator(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    Ok(nterm(NTermType::ConstantDeclarator, vec![
+        assert_term_with_type(tokens, TokenType::Ident)?,
+        parse_constant_declarator_rest(tokens, src)?]))
+}
+
+/// Parses '[throws QualifiedIdentifierList] ;'. Since Java 8, default, static
+/// and private interface methods have a Block instead of the ';'.
+fn parse_interface_method_end(tokens: &mut TokenIter, src: &str, children: &mut Vec<Node>)
+                              -> Result<(), ParseErr> {
+    parse_throws_maybe(tokens, src, children)?;
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::LBrace => children.push(parse_block(tokens, src)?),
+        _ => children.push(assert_term(tokens, src, TokenKind::Semi)?),
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn parse_interface_method_declarator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_formal_parameters(tokens, src)?];
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::LBracket {
+            children.push(term(tokens.next().unwrap()));
+            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
+        } else { break }
+    }
+    parse_interface_method_end(tokens, src, &mut children)?;
+    Ok(nterm(NTermType::InterfaceMethodDeclaratorRest, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_void_interface_method_declarator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_formal_parameters(tokens, src)?];
+    parse_interface_method_end(tokens, src, &mut children)?;
+    Ok(nterm(NTermType::VoidInterfaceMethodDeclaratorRest, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_interface_generic_method_decl(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_type_parameters(tokens, src)?];
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Void => children.push(term(tokens.next().unwrap())),
+        _ => children.push(parse_type(tokens, src)?),
+    }
+    children.push(assert_term_with_type(tokens, TokenType::Ident)?);
+    children.push(parse_interface_method_declarator_rest(tokens, src)?);
+    Ok(nterm(NTermType::InterfaceGenericMethodDecl, children))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    #[test]
+    fn test_parse_interface_body() {
+        let src = "{
+            int A = 1, B[] = {2};
+            ;
+            void foo(int a) throws java.io.IOException, Exception;
+            String[] bar()[];
+            <T extends Comparable<T>> T max(T a, T b);
+            default int size() { return 0; }
+            public static void main(String[] args) {}
+            interface Inner {}
+        }";
+        let node = parse_interface_body(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 8);
+        // 'default' and 'public static' are modifiers
+        assert_eq!(node.children[5].children.len(), 2);
+        assert_eq!(node.children[6].children.len(), 3);
+        let member = &node.children[4].children[0];
+        assert_eq!(member.children[0].node_type,
+                   NodeType::NTerm(NTermType::InterfaceGenericMethodDecl));
+    }
+}
@@ -0,0 +1,133 @@
+//! Parsing for lambda expressions
+
+use super::*;
+use lexer::TokenType;
+use super::expressions::parse_expression;
+use super::formal_parameters::parse_formal_parameters;
+use super::statements::parse_block;
+
+/// Returns true if a lambda expression is next, i.e. a single identifier or a
+/// parenthesised parameter list followed by '->'. The parentheses must hold a
+/// valid parameter list, so a parenthesised expression before '->', like the
+/// 'case (1) ->' label, isn't taken to be a lambda.
+pub fn is_lambda_next(tokens: &TokenIter, src: &str) -> bool {
+    let mut clone = tokens.clone();
+    match clone.next() {
+        Some(tok) if tok.token_type == TokenType::Ident =>
+            matches!(clone.next(), Some(tok) if tok.kind == TokenKind::Arrow),
+        Some(tok) if tok.kind == TokenKind::LParen => {
+            let mut clone = tokens.clone();
+            if is_inferred_parameters_next(tokens) {
+                // Skip to just after the ')'
+                for tok in clone.by_ref() {
+                    if tok.kind == TokenKind::RParen { break }
+                }
+            } else if parse_formal_parameters(&mut clone, src).is_err() {
+                return false;
+            }
+            matches!(clone.next(), Some(tok) if tok.kind == TokenKind::Arrow)
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the parenthesised parameter list next is empty or only
+/// identifiers, i.e. the parameter types are inferred.
+fn is_inferred_parameters_next(tokens: &TokenIter) -> bool {
+    let mut clone = tokens.clone();
+    clone.next(); // Skip '('
+    let mut expect_ident = true;
+    for tok in clone {
+        match tok.kind {
+            TokenKind::RParen => return true,
+            TokenKind::Comma if !expect_ident => expect_ident = true,
+            TokenKind::Ident if expect_ident => expect_ident = false,
+            _ => return false,
+        }
+    }
+    false
+}
+
+#[allow(dead_code)]
+pub fn parse_lambda_expression(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_lambda_parameters(tokens, src)?,
+                            assert_term(tokens, src, TokenKind::Arrow)?];
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::LBrace => children.push(parse_block(tokens, src)?),
+        _ => children.push(parse_expression(tokens, src)?),
+    }
+    Ok(nterm(NTermType::LambdaExpression, children))
+}
+
+/// Parses the parameters of a lambda: a single identifier, a parenthesised list
+/// of identifiers, or FormalParameters with explicit or 'var' types.
+#[allow(dead_code)]
+pub fn parse_lambda_parameters(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let children = match tokens.clone().next() {
+        Some(tok) if tok.token_type == TokenType::Ident => vec![term(tokens.next().unwrap())],
+        Some(tok) if tok.kind == TokenKind::LParen && is_inferred_parameters_next(tokens) => {
+            let mut children = vec![term(tokens.next().unwrap())];
+            while let Some(tok) = tokens.clone().next() {
+                match tok.kind {
+                    TokenKind::RParen => break,
+                    TokenKind::Comma => { tokens.next(); } // Skip ','
+                    _ => children.push(assert_term_with_type(tokens, TokenType::Ident)?),
+                }
+            }
+            children.push(assert_term(tokens, src, TokenKind::RParen)?);
+            children
+        }
+        _ => vec![parse_formal_parameters(tokens, src)?],
+    };
+    Ok(nterm(NTermType::LambdaParameters, children))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    fn parse(src: &str) -> Node {
+        let tokens = lex(src, "").unwrap();
+        let mut tokens = TokenIter::new(&tokens);
+        assert!(is_lambda_next(&tokens, src));
+        let node = parse_lambda_expression(&mut tokens, src).unwrap();
+        assert!(tokens.next().is_none());
+        node
+    }
+
+    #[test]
+    fn test_parse_lambda_expression() {
+        let node = parse("x -> foo(x)");
+        assert_eq!(node.children[0].children.len(), 1);
+        assert_eq!(node.children[2].node_type, NodeType::NTerm(NTermType::Expression));
+
+        let node = parse("() -> {}");
+        assert_eq!(node.children[0].children.len(), 2);
+        assert_eq!(node.children[2].node_type, NodeType::NTerm(NTermType::Block));
+
+        let node = parse("(a, b) -> { return a + b; }");
+        assert_eq!(node.children[0].children.len(), 4);
+
+        for src in &["(int a, String... b) -> a", "(var a, var b) -> a",
+                     "(final @Foo List<T> a) -> a"] {
+            let node = parse(src);
+            assert_eq!(node.children[0].children[0].node_type,
+                       NodeType::NTerm(NTermType::FormalParameters));
+        }
+    }
+
+    #[test]
+    fn test_is_lambda_next() {
+        let next = |src| is_lambda_next(&TokenIter::new(&lex(src, "").unwrap()), src);
+        assert!(!next("x + 1"));
+        assert!(!next("(a, b)"));
+        assert!(!next("(int) x"));
+        assert!(next("(a) -> a"));
+        assert!(next("(int a, List<T> b) -> a"));
+        // Parenthesised expressions aren't parameter lists
+        assert!(!next("(1) -> a"));
+        assert!(!next("(a + b) -> a"));
+        assert!(!next("(A.B) -> a"));
+    }
+}
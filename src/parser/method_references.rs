@@ -0,0 +1,89 @@
+//! Parsing for method and constructor references
+
+use super::*;
+use lexer::TokenType;
+use super::types::{is_basic_type, parse_non_wildcard_type_arguments, parse_type};
+
+/// Returns true if 'super::' is next.
+pub fn is_super_method_reference_next(tokens: &TokenIter) -> bool {
+    let mut clone = tokens.clone();
+    match (clone.next(), clone.next()) {
+        (Some(sup), Some(colons)) => sup.kind == TokenKind::Super
+            && colons.kind == TokenKind::ColonColon,
+        _ => false,
+    }
+}
+
+/// Returns true if a type followed by '::' is next, e.g. 'int[]::new' or
+/// 'List<String>::size'. A plain name like 'a.b::c' is ambiguous between an
+/// expression and a type, and is taken to be a type.
+pub fn is_type_method_reference_next(tokens: &TokenIter, src: &str) -> bool {
+    let mut clone = tokens.clone();
+    match (clone.next(), clone.next()) {
+        (Some(tok), Some(next)) if (tok.token_type == TokenType::Ident || is_basic_type(tok.kind))
+            && matches!(next.kind, TokenKind::ColonColon | TokenKind::Lt | TokenKind::Dot
+                        | TokenKind::LBracket) => (),
+        _ => return false,
+    }
+    let mut clone = tokens.clone();
+    parse_type(&mut clone, src).is_ok()
+        && matches!(clone.next(), Some(tok) if tok.kind == TokenKind::ColonColon)
+}
+
+/// Parses ':: [NonWildcardTypeArguments] (Identifier | new)' and builds a
+/// MethodReference with `qualifier` as its leading children. The qualifier is
+/// a Type, 'super', or a Primary and its selectors.
+#[allow(dead_code)]
+pub fn parse_method_reference(tokens: &mut TokenIter, src: &str, qualifier: Vec<Node>)
+                              -> ParseRes {
+    let mut children = qualifier;
+    children.push(assert_term(tokens, src, TokenKind::ColonColon)?);
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Lt {
+            children.push(parse_non_wildcard_type_arguments(tokens, src)?);
+        }
+    }
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::New => children.push(term(tokens.next().unwrap())),
+        _ => children.push(assert_term_with_type(tokens, TokenType::Ident)?),
+    }
+    Ok(nterm(NTermType::MethodReference, children))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+    use parser::expressions::parse_expression3;
+
+    fn parse(src: &str) -> Node {
+        let tokens = lex(src, "").unwrap();
+        let mut tokens = TokenIter::new(&tokens);
+        let node = parse_expression3(&mut tokens, src).unwrap();
+        assert!(tokens.next().is_none());
+        assert_eq!(node.children[0].node_type, NodeType::NTerm(NTermType::MethodReference));
+        node.children[0].clone()
+    }
+
+    #[test]
+    fn test_parse_method_reference() {
+        let node = parse("String::valueOf");
+        assert_eq!(node.children[0].node_type, NodeType::NTerm(NTermType::Type));
+        assert_eq!(node.children[2].val("String::valueOf"), Some("valueOf"));
+
+        let node = parse("this::handle");
+        assert_eq!(node.children[0].node_type, NodeType::NTerm(NTermType::Primary));
+
+        let node = parse("foo().bar::baz");
+        assert_eq!(node.children[1].node_type, NodeType::NTerm(NTermType::Selector));
+
+        let node = parse("super::<T>toString");
+        assert_eq!(node.children[2].node_type, NodeType::NTerm(NTermType::TypeArguments));
+
+        for src in &["int[]::new", "ArrayList<String>::new", "java.util.List<T>[]::new"] {
+            let node = parse(src);
+            assert_eq!(node.children[0].node_type, NodeType::NTerm(NTermType::Type));
+            assert_eq!(node.children.last().unwrap().val(src), Some("new"));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use lexer::Token;
+use lexer::{Token, TokenKind, Point};
 
 mod error;
 mod node;
@@ -17,27 +17,187 @@ mod try_catches;
 mod atoms;
 mod compilation_units;
 mod classes;
+mod interfaces;
+mod enums;
+mod annotation_types;
+mod lambdas;
+mod method_references;
+mod records;
 mod variables;
 mod formal_parameters;
 
 pub use self::error::*;
 pub use self::node::*;
 
+use self::annotations::parse_annotations;
+use self::compilation_units::{is_type_declaration_start, parse_type_declaration};
+use self::identifiers::parse_qualified_identifier;
+use self::imports::parse_import;
+
 use std;
 
-type TokenIter<'a> = std::slice::Iter<'a, Token>;
+/// An iterator over the tokens being parsed. Compound tokens starting with '>'
+/// (like '>>') can be split one '>' at a time, so nested generics can be
+/// closed - see `next_closing_angle`.
+#[derive(Clone)]
+pub struct TokenIter<'a> {
+    tokens: std::slice::Iter<'a, Token>,
+    /// What's left of a token which has had a '>' split off
+    split: Option<Token>,
+}
+
+impl<'a> TokenIter<'a> {
+    pub fn new(tokens: &'a [Token]) -> TokenIter<'a> {
+        TokenIter { tokens: tokens.iter(), split: None }
+    }
+
+    /// Consumes a single '>' if the next token is, or starts with, '>'. A
+    /// compound token like '>>=' is split, leaving '>=' as the next token.
+    pub fn next_closing_angle(&mut self) -> Option<Token> {
+        let tok = self.clone().next()?;
+        if tok.kind == TokenKind::Gt {
+            return self.next();
+        }
+        let rest = tok.kind.split_gt()?;
+        self.next();
+        let mid = Point(tok.start.0 + 1);
+        self.split = Some(Token { start: mid, full_start: mid, kind: rest, .. tok });
+        Some(Token { full_start: tok.full_start,
+                     .. Token::new_op(tok.start.0, mid.0, TokenKind::Gt) })
+    }
+}
+
+impl<'a> Iterator for TokenIter<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        match self.split.take() {
+            Some(tok) => Some(tok),
+            None => self.tokens.next().cloned(),
+        }
+    }
+}
+
 type ParseRes = Result<Node, ParseErr>;
 
-fn parse_compilation_unit(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    // Check if this is a control or stmt
-    let children = Vec::new();
-    Ok(Node {
-        node_type: NodeType::NTerm(NTermType::CompilationUnit),
-        children: children
-    })
+/// Returns true if a package declaration is next. Annotations at the start of
+/// a file may be on the package, or on the first type declaration.
+fn is_package_next(tokens: &TokenIter, src: &str) -> bool {
+    let mut clone = tokens.clone();
+    if let Some(tok) = clone.clone().next() {
+        if tok.kind == TokenKind::At && parse_annotations(&mut clone, src).is_err() {
+            return false;
+        }
+    }
+    matches!(clone.next(), Some(tok) if tok.kind == TokenKind::Package)
+}
+
+fn parse_compilation_unit(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+
+    // [[Annotations] package QualifiedIdentifier ;]
+    if is_package_next(tokens, src) {
+        if let Some(tok) = tokens.clone().next() {
+            if tok.kind == TokenKind::At {
+                children.push(parse_annotations(tokens, src)?);
+            }
+        }
+        children.push(assert_term(tokens, src, TokenKind::Package)?);
+        children.push(parse_qualified_identifier(tokens, src)?);
+        children.push(assert_term(tokens, src, TokenKind::Semi)?);
+    }
+
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Import {
+            children.push(parse_import(tokens, src)?);
+        } else { break }
+    }
+
+    // Type declarations run to the end of the file, so anything else left over
+    // is an error
+    while let Some(tok) = tokens.clone().next() {
+        if is_type_declaration_start(&tok) {
+            children.push(parse_type_declaration(tokens, src)?);
+        } else {
+            return Err(ParseErr::Point("Unexpected token, expected type declaration".to_owned(),
+                                       tok));
+        }
+    }
+
+    Ok(nterm(NTermType::CompilationUnit, children))
 }
 
 pub fn parse(tokens: &[Token], src: &str) -> ParseRes {
     debug_assert!(!tokens.is_empty());
-    parse_compilation_unit(&mut tokens.iter(), src)
+    parse_compilation_unit(&mut TokenIter::new(tokens), src)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    fn child_types(node: &Node) -> Vec<Option<NTermType>> {
+        node.children.iter().map(|c| match c.node_type {
+            NodeType::NTerm(ref t) => Some(t.clone()),
+            NodeType::Term(_) => None,
+        }).collect()
+    }
+
+    #[test]
+    fn test_parse_package_and_imports() {
+        let src = "package com.tom; import java.util.List; import static java.util.Arrays.*; ;;";
+        let node = parse(&lex(src, "").unwrap(), src).unwrap();
+        assert_eq!(node.node_type, NodeType::NTerm(NTermType::CompilationUnit));
+        assert_eq!(child_types(&node), vec![
+            None, Some(NTermType::QualifiedIdentifier), None,
+            Some(NTermType::ImportDeclaration), Some(NTermType::ImportDeclaration),
+            Some(NTermType::TypeDeclaration), Some(NTermType::TypeDeclaration)]);
+        assert_eq!(node.children[0].val(src), Some("package"));
+
+        let src = "@Deprecated @SuppressWarnings(\"all\") package com.tom;";
+        let node = parse(&lex(src, "").unwrap(), src).unwrap();
+        assert_eq!(child_types(&node), vec![
+            Some(NTermType::Annotations), None, Some(NTermType::QualifiedIdentifier), None]);
+    }
+
+    #[test]
+    fn test_parse_leftover_tokens() {
+        let src = "package a; import b.C; package d;";
+        match parse(&lex(src, "").unwrap(), src) {
+            Err(ParseErr::Point(_, tok)) => assert_eq!(tok.val(src), "package"),
+            res => panic!("Expected error, got {:?}", res),
+        }
+        let src = "import b.C; x";
+        assert!(parse(&lex(src, "").unwrap(), src).is_err());
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let src = "package a; public record A(int a) {} record B() implements I { record C() {} }";
+        let node = parse(&lex(src, "").unwrap(), src).unwrap();
+        assert_eq!(child_types(&node)[3..], [Some(NTermType::TypeDeclaration),
+                                            Some(NTermType::TypeDeclaration)]);
+    }
+
+    #[test]
+    fn test_parse_main() {
+        let src = include_str!("../../res/test-src/com/tom/Main.java");
+        let node = parse(&lex(src, "").unwrap(), src).unwrap();
+        let types = child_types(&node);
+        assert_eq!(&types[..2], &[Some(NTermType::ImportDeclaration),
+                                  Some(NTermType::ImportDeclaration)]);
+        assert_eq!(&types[2..], &vec![Some(NTermType::TypeDeclaration); 3][..]);
+    }
+
+    #[test]
+    fn test_parse_person() {
+        let src = include_str!("../../res/test-src/com/tom/Person.java");
+        let node = parse(&lex(src, "").unwrap(), src).unwrap();
+        assert_eq!(child_types(&node), vec![Some(NTermType::TypeDeclaration)]);
+        // ClassBody drops its braces, so the last token is the '}' closing
+        // the last method
+        let (_, end) = node.span().unwrap();
+        assert_eq!(&src[end.0..].trim(), &"}");
+    }
 }
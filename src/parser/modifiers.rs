@@ -1,27 +1,59 @@
 use super::*;
+use lexer::ContextualKeyword;
 use parser::annotations::parse_annotation;
 
-pub fn is_modifier_key(s: &str) -> bool {
-    s == "public" || s == "protected" || s == "private" ||
-        s == "static " || s == "abstract" || s == "final" || s == "native" ||
-        s == "synchronized" || s == "transient" || s == "volatile" || s == "strictfp"
+/// Returns true if the token is a modifier keyword.
+pub fn is_modifier_key(tok: &Token) -> bool {
+    use lexer::TokenKind::*;
+    matches!(tok.kind, Public | Protected | Private | Static | Abstract | Final | Native
+             | Synchronized | Transient | Volatile | Strictfp)
 }
 
-pub fn is_modifier_or_annot(s: &str) -> bool {
-    s == "@" || is_modifier_key(s)
+/// Returns true if the token is 'sealed', or the 'non' of 'non-sealed'. They're
+/// lexed as identifiers and are only modifiers in front of a class or interface
+/// declaration.
+pub fn is_sealed_key(tok: &Token) -> bool {
+    tok.is_contextual_key(ContextualKeyword::Sealed)
+        || tok.is_contextual_key(ContextualKeyword::NonSealed)
+}
+
+pub fn is_modifier_or_annot(tok: &Token) -> bool {
+    tok.kind == TokenKind::At || is_modifier_key(tok)
 }
 
 #[allow(dead_code)]
 pub fn parse_modifier(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let child = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "@" => parse_annotation(tokens, src)?,
-        Some(tok) if is_modifier_key(tok.val(src)) => term(*tokens.next().unwrap()),
-        Some(tok) => return Err(ParseErr::Point("Expected annotation or modifier".to_owned(), *tok)),
+        Some(tok) if tok.kind == TokenKind::At => parse_annotation(tokens, src)?,
+        Some(tok) if is_modifier_key(&tok) => term(tokens.next().unwrap()),
+        Some(tok) => return Err(ParseErr::Point("Expected annotation or modifier".to_owned(), tok)),
         None => return Err(ParseErr::Raw("Unexpected EOF, expected annotation or modifier".to_owned())),
     };
     Ok(nterm(NTermType::Modifier, vec![child]))
 }
 
+/// Parses a modifier of a class or interface declaration, which may also be
+/// 'sealed' or 'non-sealed'. 'non-sealed' is lexed as 'non', '-' and 'sealed',
+/// which are joined into a single terminal.
+#[allow(dead_code)]
+pub fn parse_class_modifier(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let tok = match tokens.clone().next() {
+        Some(tok) if tok.is_contextual_key(ContextualKeyword::Sealed) => tokens.next().unwrap(),
+        Some(tok) if tok.is_contextual_key(ContextualKeyword::NonSealed) => {
+            tokens.next();
+            assert_term(tokens, src, TokenKind::Minus)?;
+            let sealed = match tokens.next() {
+                Some(sealed) if sealed.is_contextual_key(ContextualKeyword::Sealed) => sealed,
+                Some(tok) => return Err(ParseErr::Point("Expected 'sealed'".to_owned(), tok)),
+                None => return Err(ParseErr::Raw("Expected 'sealed', got EOF".to_owned())),
+            };
+            Token { end: sealed.end, full_end: sealed.full_end, .. tok }
+        }
+        _ => return parse_modifier(tokens, src),
+    };
+    Ok(nterm(NTermType::Modifier, vec![term(tok)]))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -30,16 +62,32 @@ mod tests {
     #[test]
     fn test_parse_modifier() {
         let src = "public";
-        let node = parse_modifier(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_modifier(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         assert_eq!(node.children[0].val(src).unwrap(), "public");
 
         let src = "@MyAnnotation";
-        let node = parse_modifier(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_modifier(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         match node.children[0].node_type {
             NodeType::NTerm(NTermType::Annotation) => (),
             _ => panic!("Incorrect ntermtype when parsing an annotation modifier")
         }
     }
+
+    #[test]
+    fn test_parse_class_modifier() {
+        for src in &["sealed", "non-sealed", "final"] {
+            let node = parse_class_modifier(&mut TokenIter::new(&lex(src, "").unwrap()), src)
+                .unwrap();
+            assert_eq!(node.children[0].val(src), Some(*src));
+        }
+        for src in &["sealed", "non-sealed"] {
+            assert!(parse_modifier(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_err());
+        }
+        for src in &["permits", "non - sealed"] {
+            assert!(parse_class_modifier(&mut TokenIter::new(&lex(src, "").unwrap()), src)
+                    .is_err());
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use lexer::{Token, TokenType};
+use lexer::{Token, TokenType, TokenKind, Point};
 
 use super::*;
 
@@ -39,6 +39,15 @@ pub enum NTermType {
     AnnotationTypeBody, AnnotationTypeElementDeclarations,
     AnnotationTypeElementDeclaration, AnnotationTypeElementRest,
     AnnotationMethodOrConstantRest, AnnotationMethodRest,
+    // Java 8+
+    LambdaExpression, LambdaParameters, MethodReference,
+    // Java 14+
+    SwitchExpression, SwitchRules, SwitchRule,
+    // Java 16+
+    RecordDeclaration, RecordHeader, RecordComponent, RecordBody,
+    CompactConstructorDeclaration,
+    // Java 17+
+    Permits,
 }
 
 #[derive(Eq, PartialEq, Debug, Clone)]
@@ -63,6 +72,32 @@ impl<'a> Node {
             _ => None
         }
     }
+
+    /// Returns the first token in this node, or None if it has no terminals.
+    pub fn first_token(&self) -> Option<Token> {
+        match self.node_type {
+            NodeType::Term(t) => Some(t),
+            _ => self.children.iter().filter_map(|c| c.first_token()).next(),
+        }
+    }
+
+    /// Returns the last token in this node, or None if it has no terminals.
+    pub fn last_token(&self) -> Option<Token> {
+        match self.node_type {
+            NodeType::Term(t) => Some(t),
+            _ => self.children.iter().rev().filter_map(|c| c.last_token()).next(),
+        }
+    }
+
+    /// Returns the start and end of the source this node was parsed from, or
+    /// None if it has no terminals. Use a `SourceMap` to turn these into lines
+    /// and columns.
+    pub fn span(&self) -> Option<(Point, Point)> {
+        match (self.first_token(), self.last_token()) {
+            (Some(first), Some(last)) => Some((first.start, last.end)),
+            _ => None,
+        }
+    }
 }
 
 /// Helper function to create a terminal from a token
@@ -76,21 +111,21 @@ pub fn term(tok: Token) -> Node {
 pub fn nterm(n_term_type: NTermType, children: Vec<Node>) -> Node {
     Node {
         node_type: NodeType::NTerm(n_term_type),
-        children: children,
+        children,
     }
 }
 
-/// Returns the first token as a terminal given that its value matches the given
-/// string. Consumes the token if it matches.
-pub fn assert_term(tokens: &mut TokenIter, src: &str, expected: &str) -> ParseRes {
+/// Returns the first token as a terminal given that it's of the given kind.
+/// Consumes the token if it matches.
+pub fn assert_term(tokens: &mut TokenIter, src: &str, expected: TokenKind) -> ParseRes {
     let tok = tokens.clone().next();
     match tok {
-        Some(tok) => if tok.val(src) == expected {
-            Ok(term(*tokens.next().unwrap()))
+        Some(tok) => if tok.kind == expected {
+            Ok(term(tokens.next().unwrap()))
         } else {
-            Err(ParseErr::Point(format!("Expected {}, got {}", expected, tok.val(src)), *tok))
+            Err(ParseErr::Point(format!("Expected {}, got {}", expected.text(), tok.val(src)), tok))
         }
-        None => Err(ParseErr::Raw(format!("Expected {}, got EOF", expected))),
+        None => Err(ParseErr::Raw(format!("Expected {}, got EOF", expected.text()))),
     }
 }
 
@@ -100,10 +135,27 @@ pub fn assert_term_with_type(tokens: &mut TokenIter, expected: TokenType) -> Par
     let tok = tokens.clone().next();
     match tok {
         Some(tok) => if tok.token_type == expected {
-            Ok(term(*tokens.next().unwrap()))
+            Ok(term(tokens.next().unwrap()))
         } else {
-            Err(ParseErr::Point(format!("Expected {:?}", expected), *tok))
+            Err(ParseErr::Point(format!("Expected {:?}", expected), tok))
         }
         None => Err(ParseErr::Raw(format!("Expected {:?}, got EOF", expected))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lexer::{lex, SourceMap};
+    use parser::identifiers::parse_qualified_identifier;
+    use parser::TokenIter;
+
+    #[test]
+    fn test_span() {
+        let src = "\n  com.tom.Foo";
+        let node = parse_qualified_identifier(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let (start, end) = node.span().unwrap();
+        assert_eq!(&src[start.0..end.0], "com.tom.Foo");
+        let lc = SourceMap::new(src).line_col(start);
+        assert_eq!((lc.line, lc.col_utf16), (1, 2));
+    }
+}
@@ -0,0 +1,194 @@
+//! Parsing for record declarations
+
+use super::*;
+use lexer::{ContextualKeyword, TokenType};
+use super::annotations::parse_annotation;
+use super::classes::parse_class_body_declaration;
+use super::modifiers::{is_modifier_or_annot, parse_modifier};
+use super::statements::parse_block;
+use super::types::{parse_type, parse_type_list, parse_type_parameters};
+
+/// Returns true if a record declaration is next. 'record' is only a keyword
+/// when followed by the record's name and its header or type parameters.
+pub fn is_record_next(tokens: &TokenIter) -> bool {
+    let mut clone = tokens.clone();
+    match (clone.next(), clone.next(), clone.next()) {
+        (Some(record), Some(name), Some(next)) =>
+            record.is_contextual_key(ContextualKeyword::Record)
+            && name.token_type == TokenType::Ident
+            && (next.kind == TokenKind::LParen || next.kind == TokenKind::Lt),
+        _ => false,
+    }
+}
+
+/// Returns true if a compact canonical constructor is next, i.e. the record's
+/// name followed by a block, possibly after some modifiers.
+fn is_compact_constructor_next(tokens: &TokenIter, src: &str) -> bool {
+    let mut clone = tokens.clone();
+    while let Some(tok) = clone.clone().next() {
+        if is_modifier_or_annot(&tok) {
+            if parse_modifier(&mut clone, src).is_err() {
+                return false;
+            }
+        } else { break }
+    }
+    match (clone.next(), clone.next()) {
+        (Some(name), Some(brace)) => name.token_type == TokenType::Ident
+            && brace.kind == TokenKind::LBrace,
+        _ => false,
+    }
+}
+
+#[allow(dead_code)]
+pub fn parse_record_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let record = match tokens.next() {
+        Some(tok) if tok.is_contextual_key(ContextualKeyword::Record) => term(tok),
+        Some(tok) => return Err(ParseErr::Point("Expected 'record'".to_owned(), tok)),
+        None => return Err(ParseErr::Raw("Expected 'record', got EOF".to_owned())),
+    };
+    let mut children = vec![record, assert_term_with_type(tokens, TokenType::Ident)?];
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Lt {
+            children.push(parse_type_parameters(tokens, src)?);
+        }
+    }
+    children.push(parse_record_header(tokens, src)?);
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Implements {
+            children.push(term(tokens.next().unwrap()));
+            children.push(parse_type_list(tokens, src)?);
+        }
+    }
+    children.push(parse_record_body(tokens, src)?);
+    Ok(nterm(NTermType::RecordDeclaration, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_record_header(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![assert_term(tokens, src, TokenKind::LParen)?];
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::RParen => (),
+        _ => {
+            children.push(parse_record_component(tokens, src)?);
+            while let Some(tok) = tokens.clone().next() {
+                if tok.kind == TokenKind::Comma {
+                    tokens.next(); // Skip ','
+                    children.push(parse_record_component(tokens, src)?);
+                } else { break }
+            }
+        }
+    }
+    children.push(assert_term(tokens, src, TokenKind::RParen)?);
+    Ok(nterm(NTermType::RecordHeader, children))
+}
+
+/// Parses '{Annotation} Type [...] Identifier'. Only the last component may be
+/// varargs, but that's left to later checks.
+#[allow(dead_code)]
+pub fn parse_record_component(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::At {
+            children.push(parse_annotation(tokens, src)?);
+        } else { break }
+    }
+    children.push(parse_type(tokens, src)?);
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Ellipsis {
+            children.push(term(tokens.next().unwrap()));
+        }
+    }
+    children.push(assert_term_with_type(tokens, TokenType::Ident)?);
+    Ok(nterm(NTermType::RecordComponent, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_record_body(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    assert_term(tokens, src, TokenKind::LBrace)?;
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        match tok.kind {
+            TokenKind::RBrace => break,
+            _ if is_compact_constructor_next(tokens, src) =>
+                children.push(parse_compact_constructor_declaration(tokens, src)?),
+            _ => children.push(parse_class_body_declaration(tokens, src)?),
+        }
+    }
+    assert_term(tokens, src, TokenKind::RBrace)?;
+    Ok(nterm(NTermType::RecordBody, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_compact_constructor_declaration(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        if is_modifier_or_annot(&tok) {
+            children.push(parse_modifier(tokens, src)?);
+        } else { break }
+    }
+    children.push(assert_term_with_type(tokens, TokenType::Ident)?);
+    children.push(parse_block(tokens, src)?);
+    Ok(nterm(NTermType::CompactConstructorDeclaration, children))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    fn nterm_type(node: &Node) -> Option<NTermType> {
+        match node.node_type {
+            NodeType::NTerm(ref t) => Some(t.clone()),
+            NodeType::Term(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_record_declaration() {
+        let src = "record Pair<A, B>(@NonNull A first, B second) implements Foo, Bar {
+            public Pair {
+                Objects.requireNonNull(first);
+            }
+            Pair(A first) { this(first, null); }
+            static int count;
+            record Inner(int... xs) {}
+        }";
+        let tokens = lex(src, "").unwrap();
+        let mut tokens = TokenIter::new(&tokens);
+        assert!(is_record_next(&tokens));
+        let node = parse_record_declaration(&mut tokens, src).unwrap();
+        let types: Vec<_> = node.children.iter().map(nterm_type).collect();
+        assert_eq!(types, vec![None, None, Some(NTermType::TypeParameters),
+                               Some(NTermType::RecordHeader), None, Some(NTermType::TypeList),
+                               Some(NTermType::RecordBody)]);
+        let header = &node.children[3];
+        assert_eq!(header.children.len(), 4);
+        assert_eq!(header.children[1].children.len(), 3);
+
+        let body: Vec<_> = node.children[6].children.iter().map(nterm_type).collect();
+        assert_eq!(body, vec![Some(NTermType::CompactConstructorDeclaration),
+                              Some(NTermType::ClassBodyDeclaration),
+                              Some(NTermType::ClassBodyDeclaration),
+                              Some(NTermType::ClassBodyDeclaration)]);
+        assert_eq!(node.children[6].children[0].children.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_record_component() {
+        let src = "final String... names";
+        assert!(parse_record_component(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_err());
+        let src = "@A @B(1) List<String>... names";
+        let node = parse_record_component(&mut TokenIter::new(&lex(src, "").unwrap()), src)
+            .unwrap();
+        assert_eq!(node.children.len(), 5);
+    }
+
+    #[test]
+    fn test_is_record_next() {
+        let next = |src| is_record_next(&TokenIter::new(&lex(src, "").unwrap()));
+        assert!(next("record A() {}"));
+        assert!(next("record A<T>(T t) {}"));
+        assert!(!next("record = 1;"));
+        assert!(!next("record.foo();"));
+    }
+}
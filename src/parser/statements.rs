@@ -1,26 +1,25 @@
 use super::*;
-use super::compilation_units::parse_class_or_interface_declaration;
-use lexer::TokenType;
+use super::compilation_units::{is_class_or_interface_next, parse_class_or_interface_declaration};
+use lexer::{ContextualKeyword, TokenType};
 use super::atoms::parse_par_expression;
-use super::switches::parse_switch_block_statement_groups;
-use super::expressions::parse_expression;
+use super::switches::parse_switch_body;
+use super::expressions::{is_assignment_op, parse_expression};
 use super::for_loops::parse_for_control;
-use super::modifiers::is_modifier_or_annot;
 use super::types::{is_basic_type, parse_type};
 use super::try_catches::{parse_resource_specification,
                          parse_catches,
                          parse_finally};
 use super::variables::{parse_variable_modifier, parse_variable_declarators};
 
-pub fn is_variable_modifier(s: &str) -> bool {
-    s == "final" || s == "@"
+pub fn is_variable_modifier(kind: TokenKind) -> bool {
+    kind == TokenKind::Final || kind == TokenKind::At
 }
 
 #[allow(dead_code)]
 pub fn parse_local_variable_declaration_statement(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = Vec::new();
     while let Some(tok) = tokens.clone().next() {
-        if is_variable_modifier(tok.val(src)) {
+        if is_variable_modifier(tok.kind) {
             children.push(parse_variable_modifier(tokens, src)?);
         } else { break }
     }
@@ -32,16 +31,13 @@ pub fn parse_local_variable_declaration_statement(tokens: &mut TokenIter, src: &
 pub fn parse_block_statement(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut clone = tokens.clone();
     let children = match clone.next() {
-        Some(tok) if
-        is_modifier_or_annot(tok.val(src))
-            || tok.val(src) == "class"
-            || tok.val(src) == "interface"
-            || tok.val(src) == "enum"
+        // 'final' and annotations can be on local classes or local variables
+        Some(_) if is_class_or_interface_next(tokens, src)
             => vec![parse_class_or_interface_declaration(tokens, src)?],
-        Some(tok) if is_variable_modifier(tok.val(src)) ||
-            is_basic_type(tok.val(src)) => vec![
+        Some(tok) if is_variable_modifier(tok.kind) ||
+            is_basic_type(tok.kind) => vec![
                 parse_local_variable_declaration_statement(tokens, src)?,
-                assert_term(tokens, src, ";")?],
+                assert_term(tokens, src, TokenKind::Semi)?],
         _ => vec![parse_statement(tokens, src)?],
     };
     Ok(nterm(NTermType::BlockStatement, children))
@@ -52,7 +48,9 @@ pub fn parse_block_statements(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = Vec::new();
     loop {
         match tokens.clone().next() {
-            Some(tok) if tok.val(src) == "}" => break,
+            // In a switch block, 'case' and 'default' end the statements
+            Some(tok) if tok.kind == TokenKind::RBrace || tok.kind == TokenKind::Case
+                || tok.kind == TokenKind::Default => break,
             _ => children.push(parse_block_statement(tokens, src)?),
         }
     }
@@ -62,103 +60,127 @@ pub fn parse_block_statements(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_block(tokens: &mut TokenIter, src: &str) -> ParseRes {
     Ok(nterm(NTermType::Block,
-          vec![assert_term(tokens, src, "{")?,
+          vec![assert_term(tokens, src, TokenKind::LBrace)?,
                parse_block_statements(tokens, src)?,
-               assert_term(tokens, src, "}")?]))
+               assert_term(tokens, src, TokenKind::RBrace)?]))
+}
+
+/// Returns true if a 'yield' statement is next. 'yield' is only a keyword at
+/// the start of a statement, and not when it's used as a variable, e.g.
+/// 'yield = 1;' or 'yield.foo();'.
+fn is_yield_next(tokens: &TokenIter) -> bool {
+    let mut clone = tokens.clone();
+    match (clone.next(), clone.next()) {
+        (Some(tok), Some(next)) => tok.is_contextual_key(ContextualKeyword::Yield)
+            && !is_assignment_op(next.kind)
+            && !matches!(next.kind, TokenKind::Dot | TokenKind::LBracket | TokenKind::Colon
+                         | TokenKind::ColonColon | TokenKind::Arrow | TokenKind::Semi
+                         | TokenKind::PlusPlus | TokenKind::MinusMinus),
+        _ => false,
+    }
 }
 
 #[allow(dead_code)]
 pub fn parse_statement(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut clone = tokens.clone();
-    let children = match clone.next() {
-        Some(tok) if tok.val(src) == "{" => vec![parse_block(tokens, src)?],
-        Some(tok) if tok.val(src) == ";" => vec![term(*tokens.next().unwrap())],
-        Some(tok) if tok.token_type == TokenType::Ident => match clone.next() {
-            Some(tok) if tok.val(src) == ":" => vec![
-                term(*tokens.next().unwrap()), // Ident
-                term(*tokens.next().unwrap()), // ":"
+    let tok = match clone.next() {
+        Some(tok) => tok,
+        None => return Err(ParseErr::Raw("Expected statement, found EOF".to_owned())),
+    };
+    let children = match tok.kind {
+        TokenKind::LBrace => vec![parse_block(tokens, src)?],
+        TokenKind::Semi => vec![term(tokens.next().unwrap())],
+        TokenKind::Ident if is_yield_next(tokens) => vec![
+            term(tokens.next().unwrap()),
+            parse_expression(tokens, src)?,
+            assert_term(tokens, src, TokenKind::Semi)?],
+        TokenKind::Ident => match clone.next() {
+            Some(tok) if tok.kind == TokenKind::Colon => vec![
+                term(tokens.next().unwrap()), // Ident
+                term(tokens.next().unwrap()), // ":"
                 parse_statement(tokens, src)?],
-            _ => vec![parse_statement_expression(tokens, src)?, assert_term(tokens, src, ";")?],
+            _ => vec![parse_statement_expression(tokens, src)?, assert_term(tokens, src, TokenKind::Semi)?],
         }
-        Some(tok) if tok.val(src) == "if" => {
+        TokenKind::If => {
             let mut children = vec![
-            term(*tokens.next().unwrap()),
+            term(tokens.next().unwrap()),
             parse_par_expression(tokens, src)?,
             parse_statement(tokens, src)?];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "else" => {
-                    children.push(term(*tokens.next().unwrap()));
+                Some(tok) if tok.kind == TokenKind::Else => {
+                    children.push(term(tokens.next().unwrap()));
                     children.push(parse_statement(tokens, src)?);
                 }
                 _ => ()
             }
             children
         }
-        Some(tok) if tok.val(src) == "assert" => {
-            let mut children = vec![term(*tokens.next().unwrap()),
+        TokenKind::Assert => {
+            let mut children = vec![term(tokens.next().unwrap()),
                                     parse_expression(tokens, src)?];
             while let Some(tok) = tokens.clone().next() {
-                if tok.val(src) == ":" {
+                if tok.kind == TokenKind::Colon {
                     tokens.next().unwrap();
                     children.push(parse_expression(tokens, src)?);
                 } else { break }
             }
             children
         },
-        Some(tok) if tok.val(src) == "switch" => vec![
-            term(*tokens.next().unwrap()),
+        TokenKind::Switch => vec![
+            term(tokens.next().unwrap()),
             parse_par_expression(tokens, src)?,
-            assert_term(tokens, src, "{")?,
-            parse_switch_block_statement_groups(tokens, src)?,
-            assert_term(tokens, src, "}")?],
-        Some(tok) if tok.val(src) == "while" => vec![
-            term(*tokens.next().unwrap()),
+            assert_term(tokens, src, TokenKind::LBrace)?,
+            parse_switch_body(tokens, src)?,
+            assert_term(tokens, src, TokenKind::RBrace)?],
+        TokenKind::While => vec![
+            term(tokens.next().unwrap()),
             parse_par_expression(tokens, src)?,
             parse_statement(tokens, src)?,
             ],
-        Some(tok) if tok.val(src) == "do" => vec![
-            term(*tokens.next().unwrap()),
+        TokenKind::Do => vec![
+            term(tokens.next().unwrap()),
             parse_statement(tokens, src)?,
-            assert_term(tokens, src, "while")?,
+            assert_term(tokens, src, TokenKind::While)?,
             parse_par_expression(tokens, src)?,
-            assert_term(tokens, src, ";")?],
-        Some(tok) if tok.val(src) == "for" => vec![
-            term(*tokens.next().unwrap()),
-            assert_term(tokens, src, "(")?,
+            assert_term(tokens, src, TokenKind::Semi)?],
+        TokenKind::For => vec![
+            term(tokens.next().unwrap()),
+            assert_term(tokens, src, TokenKind::LParen)?,
             parse_for_control(tokens, src)?,
-            assert_term(tokens, src, ")")?,
+            assert_term(tokens, src, TokenKind::RParen)?,
             parse_statement(tokens, src)?],
-        Some(tok) if tok.val(src) == "break" || tok.val(src) == "continue" => {
-            let mut children = vec![term(*tokens.next().unwrap())];
+        TokenKind::Break | TokenKind::Continue => {
+            let mut children = vec![term(tokens.next().unwrap())];
             match tokens.clone().next() {
                 Some(tok) if tok.token_type == TokenType::Ident => {
-                    children.push(term(*tokens.next().unwrap()));
+                    children.push(term(tokens.next().unwrap()));
                 }
                 _ => ()
             }
-            children.push(assert_term(tokens, src, ";")?);
+            children.push(assert_term(tokens, src, TokenKind::Semi)?);
             children
         }
-        Some(tok) if tok.val(src) == "return" => {
-            let mut children = vec![term(*tokens.next().unwrap())];
+        TokenKind::Return => {
+            let mut children = vec![term(tokens.next().unwrap())];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) != ";" => {
+                Some(tok) if tok.kind != TokenKind::Semi => {
                     children.push(parse_expression(tokens, src)?);
                 }
                 _ => ()
             }
-            children.push(assert_term(tokens, src, ";")?);
+            children.push(assert_term(tokens, src, TokenKind::Semi)?);
             children
         }
-        Some(tok) if tok.val(src) == "throw" => vec![
-            term(*tokens.next().unwrap()),
-            parse_expression(tokens, src)?],
-        Some(tok) if tok.val(src) == "synchronized" => vec![
-            term(*tokens.next().unwrap()),
+        TokenKind::Throw => vec![
+            term(tokens.next().unwrap()),
+            parse_expression(tokens, src)?,
+            assert_term(tokens, src, TokenKind::Semi)?],
+        TokenKind::Synchronized => vec![
+            term(tokens.next().unwrap()),
             parse_par_expression(tokens, src)?,
             parse_block(tokens, src)?,
             ],
-        Some(tok) if tok.val(src) == "try" => {
+        TokenKind::Try => {
             // FIXME: So, this is actually incorrect parsing. Here is the official grammar:
             // try Block (Catches | [Catches] Finally)
             // try ResourceSpecification Block [Catches] [Finally]
@@ -167,23 +189,28 @@ pub fn parse_statement(tokens: &mut TokenIter, src: &str) -> ParseRes {
             // need to make this distinction. This is much more simple parsing
             // code, if slightly incorrect, which always allows Catches to be
             // optional:
-            let mut children = vec![term(*tokens.next().unwrap()),
+            let mut children = vec![term(tokens.next().unwrap()),
                                 match clone.next() {
-                                    Some(tok) if tok.val(src) == "{" => parse_block(tokens, src)?,
+                                    Some(tok) if tok.kind == TokenKind::LBrace => parse_block(tokens, src)?,
                                     _ => parse_resource_specification(tokens, src)?,
                                 }];
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "catch" => children.push(parse_catches(tokens, src)?),
+                Some(tok) if tok.kind == TokenKind::Catch => children.push(parse_catches(tokens, src)?),
                 _ => ()
             }
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "finally" => children.push(parse_finally(tokens, src)?),
+                Some(tok) if tok.kind == TokenKind::Finally => children.push(parse_finally(tokens, src)?),
                 _ => ()
             }
             children
         }
-        Some(tok) => return Err(ParseErr::Point("Unexpected token, expected statement".to_owned(), *tok)),
-        None => return Err(ParseErr::Raw("Expected statement, found EOF".to_owned())),
+        // Expression statements not starting with an identifier, e.g.
+        // 'this.a = a;' or '++i;'
+        TokenKind::This | TokenKind::Super | TokenKind::New | TokenKind::LParen
+            | TokenKind::PlusPlus | TokenKind::MinusMinus => vec![
+                parse_statement_expression(tokens, src)?,
+                assert_term(tokens, src, TokenKind::Semi)?],
+        _ => return Err(ParseErr::Point("Unexpected token, expected statement".to_owned(), tok)),
     };
     Ok(nterm(NTermType::Statement, children))
 }
@@ -192,8 +219,10 @@ pub fn parse_statement(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_statement_expression(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut clone = tokens.clone();
     let children = match clone.next() {
+        // Only a type can start a local variable declaration here; keywords
+        // like 'new' or 'this' followed by an identifier are expressions
         Some(tok) if tok.token_type == TokenType::Ident
-            || tok.token_type == TokenType::Key => match clone.next() {
+            || is_basic_type(tok.kind) => match clone.next() {
                 Some(tok) if tok.token_type == TokenType::Ident =>
                     vec![parse_local_variable_declaration_statement(tokens, src)?],
                 _ => vec![parse_expression(tokens, src)?],
@@ -212,8 +241,53 @@ mod tests {
     #[test]
     fn test_parse_block() {
         let src = "{Foo f = new Foo(); float f = 0.0; String s0 = \"Hello, \", s1 = \"world!\"; String hello = s0 + s1;}";
-        let node = parse_block(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_block(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
         assert_eq!(node.children[1].children.len(), 4);
     }
+
+    #[test]
+    fn test_parse_local_record() {
+        let src = "{ record Point(int x, int y) {} Point p = new Point(1, 2); record = 1; }";
+        let node = parse_block(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let stmts = &node.children[1].children;
+        assert_eq!(stmts.len(), 3);
+        assert_eq!(stmts[0].children[0].node_type,
+                   NodeType::NTerm(NTermType::ClassOrInterfaceDeclaration));
+        assert_eq!(stmts[2].children[0].node_type, NodeType::NTerm(NTermType::Statement));
+    }
+
+    #[test]
+    fn test_parse_expression_statements() {
+        let src = "{ new Foo(); new Thread(r).start(); this.a = a; ++i; }";
+        let node = parse_block(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let stmts = &node.children[1].children;
+        assert_eq!(stmts.len(), 4);
+        for stmt in stmts {
+            let expr = &stmt.children[0].children[0];
+            assert_eq!(expr.node_type, NodeType::NTerm(NTermType::StatementExpression));
+            assert_eq!(expr.children[0].node_type, NodeType::NTerm(NTermType::Expression));
+        }
+    }
+
+    #[test]
+    fn test_parse_yield() {
+        let src = "{ yield a + 1; yield = 2; yield.foo(); yield++; }";
+        let node = parse_block(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let lens: Vec<_> = node.children[1].children.iter()
+            .map(|s| s.children[0].children.len()).collect();
+        // Only the first is a yield statement, the rest are expression statements
+        assert_eq!(lens, vec![3, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_parse_local_class() {
+        let src = "{final int a = 1; @Foo final class Bar<T> implements Baz {} interface Qux {}}";
+        let node = parse_block(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let types: Vec<_> = node.children[1].children.iter()
+            .map(|s| s.children[0].node_type.clone()).collect();
+        assert_eq!(types, vec![NodeType::NTerm(NTermType::LocalVariableDeclarationStatement),
+                               NodeType::NTerm(NTermType::ClassOrInterfaceDeclaration),
+                               NodeType::NTerm(NTermType::ClassOrInterfaceDeclaration)]);
+    }
 }
@@ -1,7 +1,223 @@
 use super::*;
+use lexer::TokenType;
+use super::atoms::parse_par_expression;
+use super::expressions::parse_expression;
+use super::statements::{parse_block, parse_block_statements, parse_statement};
+
+/// Returns true if the switch label next ends in '->', i.e. the switch block
+/// is made of SwitchRules rather than SwitchBlockStatementGroups.
+fn is_switch_rule_next(tokens: &TokenIter, src: &str) -> bool {
+    match parse_switch_label(&mut tokens.clone(), src) {
+        Ok(label) => matches!(label.children.last().map(|n| &n.node_type),
+                              Some(NodeType::Term(tok)) if tok.kind == TokenKind::Arrow),
+        Err(_) => false,
+    }
+}
+
+/// Parses the contents of a switch block, which are either all arrow rules or
+/// all statement groups.
+#[allow(dead_code)]
+pub fn parse_switch_body(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    if is_switch_rule_next(tokens, src) {
+        parse_switch_rules(tokens, src)
+    } else {
+        parse_switch_block_statement_groups(tokens, src)
+    }
+}
+
+/// Parses 'switch ParExpression { SwitchBody }' used as an expression.
+#[allow(dead_code)]
+pub fn parse_switch_expression(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    Ok(nterm(NTermType::SwitchExpression, vec![
+        assert_term(tokens, src, TokenKind::Switch)?,
+        parse_par_expression(tokens, src)?,
+        assert_term(tokens, src, TokenKind::LBrace)?,
+        parse_switch_body(tokens, src)?,
+        assert_term(tokens, src, TokenKind::RBrace)?]))
+}
+
+#[allow(dead_code)]
+pub fn parse_switch_rules(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        match tok.kind {
+            TokenKind::RBrace => break,
+            _ => children.push(parse_switch_rule(tokens, src)?),
+        }
+    }
+    Ok(nterm(NTermType::SwitchRules, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_switch_rule(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_switch_label(tokens, src)?];
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::LBrace => children.push(parse_block(tokens, src)?),
+        Some(tok) if tok.kind == TokenKind::Throw => children.push(parse_statement(tokens, src)?),
+        _ => {
+            children.push(parse_expression(tokens, src)?);
+            children.push(assert_term(tokens, src, TokenKind::Semi)?);
+        }
+    }
+    Ok(nterm(NTermType::SwitchRule, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_switch_block_statement_groups(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = Vec::new();
+    while let Some(tok) = tokens.clone().next() {
+        match tok.kind {
+            TokenKind::RBrace => break,
+            _ => children.push(parse_switch_block_statement_group(tokens, src)?),
+        }
+    }
+    Ok(nterm(NTermType::SwitchBlockStatementGroups, children))
+}
+
+#[allow(dead_code)]
+pub fn parse_switch_block_statement_group(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    Ok(nterm(NTermType::SwitchBlockStatementGroup, vec![
+        parse_switch_labels(tokens, src)?,
+        parse_block_statements(tokens, src)?]))
+}
+
+#[allow(dead_code)]
+pub fn parse_switch_labels(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![parse_switch_label(tokens, src)?];
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Case || tok.kind == TokenKind::Default {
+            children.push(parse_switch_label(tokens, src)?);
+        } else { break }
+    }
+    Ok(nterm(NTermType::SwitchLabels, children))
+}
+
+/// Parses a label ending in ':', or in '->' for a SwitchRule. Since Java 14 a
+/// 'case' can have several comma-separated constants.
+#[allow(dead_code)]
+pub fn parse_switch_label(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Case => {
+            let mut children = vec![term(tokens.next().unwrap())];
+            loop {
+                let mut clone = tokens.clone();
+                match (clone.next(), clone.next()) {
+                    // An identifier on its own is taken to be an enum constant
+                    (Some(ident), Some(end)) if ident.token_type == TokenType::Ident
+                        && matches!(end.kind, TokenKind::Colon | TokenKind::Arrow
+                                    | TokenKind::Comma) =>
+                        children.push(parse_enum_constant_name(tokens, src)?),
+                    _ => children.push(parse_expression(tokens, src)?),
+                }
+                match tokens.clone().next() {
+                    Some(tok) if tok.kind == TokenKind::Comma => { tokens.next(); } // Skip ','
+                    _ => break,
+                }
+            }
+            children
+        }
+        Some(tok) if tok.kind == TokenKind::Default => vec![term(tokens.next().unwrap())],
+        Some(tok) => return Err(ParseErr::Point(
+            "Expected 'case' or 'default'".to_owned(), tok)),
+        None => return Err(ParseErr::Raw(
+            "Expected 'case' or 'default', got EOF".to_owned())),
+    };
+    match tokens.clone().next() {
+        Some(tok) if tok.kind == TokenKind::Arrow => children.push(term(tokens.next().unwrap())),
+        _ => children.push(assert_term(tokens, src, TokenKind::Colon)?),
+    }
+    Ok(nterm(NTermType::SwitchLabel, children))
+}
 
 #[allow(dead_code)]
-pub fn parse_switch_block_statement_groups(_tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    unimplemented!()
+pub fn parse_enum_constant_name(tokens: &mut TokenIter, _src: &str) -> ParseRes {
+    Ok(nterm(NTermType::EnumConstantName, vec![
+        assert_term_with_type(tokens, TokenType::Ident)?]))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::lex;
+
+    #[test]
+    fn test_parse_switch_block_statement_groups() {
+        let src = "
+            case A:
+            case 1 + 2:
+                foo();
+                break;
+            default:
+            case B: {
+                bar();
+            }
+            return;
+        }";
+        let node = parse_switch_block_statement_groups(
+            &mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children.len(), 2);
+        let labels = &node.children[0].children[0];
+        assert_eq!(labels.children.len(), 2);
+        assert_eq!(labels.children[0].children[1].node_type,
+                   NodeType::NTerm(NTermType::EnumConstantName));
+        assert_eq!(labels.children[1].children[1].node_type,
+                   NodeType::NTerm(NTermType::Expression));
+        assert_eq!(node.children[0].children[1].children.len(), 2);
+        assert_eq!(node.children[1].children[1].children.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_switch_statement() {
+        let src = "switch (a) { case 1: case 2: break; default: a++; }";
+        assert!(parse_statement(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok());
+        let src = "switch (a) {}";
+        assert!(parse_statement(&mut TokenIter::new(&lex(src, "").unwrap()), src).is_ok());
+    }
+
+    #[test]
+    fn test_parse_switch_rules() {
+        let src = "switch (a) {
+            case A, B -> foo();
+            case C -> { bar(); }
+            case 1 + 2 -> throw new Exception();
+            default -> {}
+        }";
+        let node = parse_statement(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let rules = &node.children[3];
+        assert_eq!(rules.node_type, NodeType::NTerm(NTermType::SwitchRules));
+        assert_eq!(rules.children.len(), 4);
+        // 'case', 2 constants and '->'
+        assert_eq!(rules.children[0].children[0].children.len(), 4);
+        let bodies: Vec<_> = rules.children.iter()
+            .map(|r| r.children[1].node_type.clone()).collect();
+        assert_eq!(bodies, vec![NodeType::NTerm(NTermType::Expression),
+                                NodeType::NTerm(NTermType::Block),
+                                NodeType::NTerm(NTermType::Statement),
+                                NodeType::NTerm(NTermType::Block)]);
+    }
+
+    #[test]
+    fn test_parse_parenthesised_case_constant() {
+        let src = "switch (a) { case (1) -> foo(); case (A.B), (2 + 3) -> bar(); }";
+        let node = parse_statement(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let rules = &node.children[3];
+        assert_eq!(rules.node_type, NodeType::NTerm(NTermType::SwitchRules));
+        assert_eq!(rules.children.len(), 2);
+        assert_eq!(rules.children[1].children[0].children.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_switch_expression() {
+        let src = "{
+            int a = switch (b) { case 1, 2 -> 3; default -> { yield 4; } };
+            String s = switch (c) { case A: yield \"a\"; default: yield \"b\"; };
+        }";
+        let node = parse_block(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        assert_eq!(node.children[1].children.len(), 2);
+        let src = "switch (b) { case 1 -> 2; default -> 3; }";
+        let node = parse_expression(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let switch = &node.children[0].children[0].children[0].children[0];
+        assert_eq!(switch.node_type, NodeType::NTerm(NTermType::SwitchExpression));
+        assert_eq!(switch.children[3].node_type, NodeType::NTerm(NTermType::SwitchRules));
+    }
+}
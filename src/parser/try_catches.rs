@@ -11,7 +11,7 @@ use super::variables::parse_variable_modifier;
 pub fn parse_catches(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_catch_clause(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "catch" {
+        if tok.kind == TokenKind::Catch {
             children.push(parse_catch_clause(tokens, src)?);
         } else { break }
     }
@@ -21,17 +21,17 @@ pub fn parse_catches(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_catch_clause(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![
-        assert_term(tokens, src, "catch")?,
-        assert_term(tokens, src, "(")?];
+        assert_term(tokens, src, TokenKind::Catch)?,
+        assert_term(tokens, src, TokenKind::LParen)?];
     while let Some(tok) = tokens.clone().next() {
-        match tok.val(src) {
-            "final" | "@" => children.push(parse_variable_modifier(tokens, src)?),
+        match tok.kind {
+            TokenKind::Final | TokenKind::At => children.push(parse_variable_modifier(tokens, src)?),
             _ => break
         }
     }
     children.push(parse_catch_type(tokens, src)?);
     children.push(assert_term_with_type(tokens, TokenType::Ident)?);
-    children.push(assert_term(tokens, src, ")")?);
+    children.push(assert_term(tokens, src, TokenKind::RParen)?);
     children.push(parse_block(tokens, src)?);
     Ok(nterm(NTermType::CatchClause, children))
 }
@@ -40,7 +40,7 @@ pub fn parse_catch_clause(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_catch_type(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_qualified_identifier(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "|" {
+        if tok.kind == TokenKind::Pipe {
             tokens.next().unwrap();
             children.push(parse_qualified_identifier(tokens, src)?);
         } else { break }
@@ -50,20 +50,20 @@ pub fn parse_catch_type(tokens: &mut TokenIter, src: &str) -> ParseRes {
 
 #[allow(dead_code)]
 pub fn parse_finally(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    Ok(nterm(NTermType::Finally, vec![assert_term(tokens, src, "finally")?,
+    Ok(nterm(NTermType::Finally, vec![assert_term(tokens, src, TokenKind::Finally)?,
                                       parse_block(tokens, src)?]))
 }
 
 #[allow(dead_code)]
 pub fn parse_resource_specification(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![
-        assert_term(tokens, src, "(")?,
+        assert_term(tokens, src, TokenKind::LParen)?,
         parse_resources(tokens, src)?];
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == ";" => children.push(term(*tokens.next().unwrap())),
+        Some(tok) if tok.kind == TokenKind::Semi => children.push(term(tokens.next().unwrap())),
         _ => ()
     }
-    children.push(assert_term(tokens, src, ")")?);
+    children.push(assert_term(tokens, src, TokenKind::RParen)?);
     Ok(nterm(NTermType::ResourceSpecification, children))
 }
 
@@ -71,7 +71,7 @@ pub fn parse_resource_specification(tokens: &mut TokenIter, src: &str) -> ParseR
 pub fn parse_resources(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_resource(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == ";" {
+        if tok.kind == TokenKind::Semi {
             tokens.next().unwrap();
             children.push(parse_resource(tokens, src)?);
         } else { break }
@@ -83,14 +83,14 @@ pub fn parse_resources(tokens: &mut TokenIter, src: &str) -> ParseRes {
 pub fn parse_resource(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = Vec::new();
     while let Some(tok) = tokens.clone().next() {
-        match tok.val(src) {
-            "final" | "@" => children.push(parse_variable_modifier(tokens, src)?),
+        match tok.kind {
+            TokenKind::Final | TokenKind::At => children.push(parse_variable_modifier(tokens, src)?),
             _ => break
         }
     }
     children.push(parse_reference_type(tokens, src)?);
     children.push(parse_variable_declarator_id(tokens, src)?);
-    children.push(assert_term(tokens, src, "=")?);
+    children.push(assert_term(tokens, src, TokenKind::Eq)?);
     children.push(parse_expression(tokens, src)?);
     Ok(nterm(NTermType::Resource, children))
 }
@@ -104,7 +104,7 @@ mod tests {
     pub fn test_parse_catches() {
         let src =
             "catch (IOException | SocketException e) {e.printStackTrace();} catch (Exception e) {e.printStackTrace();} ";
-        let node = parse_catches(&mut lex(src, "").unwrap().iter(), src);
+        let node = parse_catches(&mut TokenIter::new(&lex(src, "").unwrap()), src);
         let node = node.unwrap();
         assert_eq!(node.children.len(), 2);
     }
@@ -112,7 +112,7 @@ mod tests {
     #[test]
     pub fn test_parse_resource_specification() {
         let src = "(FileInputStream fis = getInputStream(); SomeOtherRes r = someFunc())";
-        let node = parse_resource_specification(&mut lex(src, "").unwrap().iter(), src);
+        let node = parse_resource_specification(&mut TokenIter::new(&lex(src, "").unwrap()), src);
         let node = node.unwrap();
         assert_eq!(node.children.len(), 3);
     }
@@ -3,25 +3,25 @@ use lexer::TokenType;
 
 #[allow(dead_code)]
 pub fn parse_basic_type(tokens: &mut TokenIter, _src: &str) -> ParseRes {
-    Ok(nterm(NTermType::BasicType, vec![term(*tokens.next().unwrap())]))
+    Ok(nterm(NTermType::BasicType, vec![term(tokens.next().unwrap())]))
 }
 
-pub fn is_basic_type(s: &str) -> bool {
-    s == "byte" || s == "int" || s == "short" || s == "char" ||
-        s == "long" || s == "float" || s == "double" || s == "boolean"
+pub fn is_basic_type(kind: TokenKind) -> bool {
+    use lexer::TokenKind::*;
+    matches!(kind, Byte | Int | Short | Char | Long | Float | Double | Boolean)
 }
 
 #[allow(dead_code)]
 pub fn parse_type(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = match tokens.clone().next().ok_or(
         ParseErr::Raw("Unexpected EOF, expected type".to_owned()))? {
-        tok if is_basic_type(tok.val(src)) => vec![parse_basic_type(tokens, src)?],
+        tok if is_basic_type(tok.kind) => vec![parse_basic_type(tokens, src)?],
         _ => vec![parse_reference_type(tokens, src)?],
     };
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "[" {
-            children.push(term(*tokens.next().unwrap()));
-            children.push(assert_term(tokens, src, "]")?);
+        if tok.kind == TokenKind::LBracket {
+            children.push(term(tokens.next().unwrap()));
+            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
         } else { break }
     }
     Ok(nterm(NTermType::Type, children))
@@ -29,14 +29,14 @@ pub fn parse_type(tokens: &mut TokenIter, src: &str) -> ParseRes {
 
 pub fn parse_reference_type(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![assert_term_with_type(tokens, TokenType::Ident)?];
-    if is_type_args_next(tokens, src) {
+    if is_type_args_next(tokens) {
         children.push(parse_type_arguments(tokens, src)?);
     }
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "." {
+        if tok.kind == TokenKind::Dot {
             tokens.next(); // Skip '.'
             children.push(assert_term_with_type(tokens, TokenType::Ident)?);
-            if is_type_args_next(tokens, src) {
+            if is_type_args_next(tokens) {
                 children.push(parse_type_arguments(tokens, src)?);
             }
         } else {
@@ -46,11 +46,8 @@ pub fn parse_reference_type(tokens: &mut TokenIter, src: &str) -> ParseRes {
     Ok(nterm(NTermType::ReferenceType, children))
 }
 
-fn is_type_args_next(tokens: &TokenIter, src: &str) -> bool {
-    match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "<" => true,
-        _ => false
-    }
+fn is_type_args_next(tokens: &TokenIter) -> bool {
+    matches!(tokens.clone().next(), Some(tok) if tok.kind == TokenKind::Lt)
 }
 
 pub fn parse_type_argument(tokens: &mut TokenIter, src: &str) -> ParseRes {
@@ -58,11 +55,11 @@ pub fn parse_type_argument(tokens: &mut TokenIter, src: &str) -> ParseRes {
     Ok(nterm(NTermType::TypeArgument,
           match clone.next().ok_or(ParseErr::Raw("Unexpected EOF in type args".to_owned()))? {
               // ? extends T
-              tok if tok.val(src) == "?" => match clone.next() {
-                  Some(tok) if tok.val(src) == "super" || tok.val(src) == "extends" =>
-                      vec![term(*tokens.next().unwrap()), term(*tokens.next().unwrap()),
+              tok if tok.kind == TokenKind::Question => match clone.next() {
+                  Some(tok) if tok.kind == TokenKind::Super || tok.kind == TokenKind::Extends =>
+                      vec![term(tokens.next().unwrap()), term(tokens.next().unwrap()),
                            parse_reference_type(tokens, src)?],
-                  _ => vec![term(*tokens.next().unwrap())],
+                  _ => vec![term(tokens.next().unwrap())],
               }
               _ => vec![parse_reference_type(tokens, src)?]
           }))
@@ -71,33 +68,46 @@ pub fn parse_type_argument(tokens: &mut TokenIter, src: &str) -> ParseRes {
 #[allow(dead_code)]
 pub fn parse_non_wildcard_type_arguments(tokens: &mut TokenIter, src: &str) -> ParseRes {
     Ok(nterm(NTermType::TypeArguments,
-             vec![assert_term(tokens, src, "<")?,
+             vec![assert_term(tokens, src, TokenKind::Lt)?,
                   parse_type_list(tokens, src)?,
-                  assert_term(tokens, src, ">")?]))
+                  parse_closing_angle(tokens, src)?]))
 }
 
 pub fn parse_type_arguments(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    let mut children = vec![assert_term(tokens, src, "<")?,
+    let mut children = vec![assert_term(tokens, src, TokenKind::Lt)?,
                             parse_type_argument(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
+        if tok.kind == TokenKind::Comma {
             tokens.next(); // Skip ','
             children.push(parse_type_argument(tokens, src)?);
         } else {
             break;
         }
     }
-    children.push(assert_term(tokens, src, ">")?);
+    children.push(parse_closing_angle(tokens, src)?);
     Ok(nterm(NTermType::TypeArguments, children))
 }
 
+/// Parses the '>' closing type arguments or parameters. This may be the start
+/// of a compound token when generics are nested, e.g. the '>>' in
+/// 'List<List<T>>', in which case the token is split.
+pub fn parse_closing_angle(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    match tokens.next_closing_angle() {
+        Some(tok) => Ok(term(tok)),
+        None => match tokens.clone().next() {
+            Some(tok) => Err(ParseErr::Point(format!("Expected >, got {}", tok.val(src)), tok)),
+            None => Err(ParseErr::Raw("Expected >, got EOF".to_owned())),
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub fn parse_non_wildcard_type_arguments_or_diamond(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut clone = tokens.clone();
     Ok(nterm(NTermType::NonWildcardTypeArgumentsOrDiamond, match clone.next() {
-        Some(tok) if tok.val(src) == "<" => match clone.next() {
-            Some(tok) if tok.val(src) == ">" => vec![term(*tokens.next().unwrap()),
-                                                     term(*tokens.next().unwrap())],
+        Some(tok) if tok.kind == TokenKind::Lt => match clone.next() {
+            Some(tok) if tok.kind == TokenKind::Gt => vec![term(tokens.next().unwrap()),
+                                                     term(tokens.next().unwrap())],
             _ => vec![parse_non_wildcard_type_arguments(tokens, src)?]
         }
         _ => vec![parse_non_wildcard_type_arguments(tokens, src)?],
@@ -108,9 +118,9 @@ pub fn parse_non_wildcard_type_arguments_or_diamond(tokens: &mut TokenIter, src:
 pub fn parse_type_arguments_or_diamond(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut clone = tokens.clone();
     Ok(nterm(NTermType::TypeArgumentsOrDiamond, match clone.next() {
-        Some(tok) if tok.val(src) == "<" => match clone.next() {
-            Some(tok) if tok.val(src) == ">" => vec![term(*tokens.next().unwrap()),
-                                                     term(*tokens.next().unwrap())],
+        Some(tok) if tok.kind == TokenKind::Lt => match clone.next() {
+            Some(tok) if tok.kind == TokenKind::Gt => vec![term(tokens.next().unwrap()),
+                                                     term(tokens.next().unwrap())],
             _ => vec![parse_type_arguments(tokens, src)?]
         }
         _ => vec![parse_type_arguments(tokens, src)?],
@@ -121,7 +131,7 @@ pub fn parse_type_arguments_or_diamond(tokens: &mut TokenIter, src: &str) -> Par
 pub fn parse_type_list(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_reference_type(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
+        if tok.kind == TokenKind::Comma {
             tokens.next(); // Skip ','
             children.push(parse_reference_type(tokens, src)?);
         } else {
@@ -131,11 +141,38 @@ pub fn parse_type_list(tokens: &mut TokenIter, src: &str) -> ParseRes {
     Ok(nterm(NTermType::TypeList, children))
 }
 
+#[allow(dead_code)]
+pub fn parse_type_parameters(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![assert_term(tokens, src, TokenKind::Lt)?,
+                            parse_type_parameter(tokens, src)?];
+    while let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Comma {
+            tokens.next(); // Skip ','
+            children.push(parse_type_parameter(tokens, src)?);
+        } else {
+            break;
+        }
+    }
+    children.push(parse_closing_angle(tokens, src)?);
+    Ok(nterm(NTermType::TypeParameters, children))
+}
+
+pub fn parse_type_parameter(tokens: &mut TokenIter, src: &str) -> ParseRes {
+    let mut children = vec![assert_term_with_type(tokens, TokenType::Ident)?];
+    if let Some(tok) = tokens.clone().next() {
+        if tok.kind == TokenKind::Extends {
+            children.push(term(tokens.next().unwrap()));
+            children.push(parse_bound(tokens, src)?);
+        }
+    }
+    Ok(nterm(NTermType::TypeParameter, children))
+}
+
 #[allow(dead_code)]
 pub fn parse_bound(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_reference_type(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "&" {
+        if tok.kind == TokenKind::Amp {
             tokens.next(); // Skip '&'
             children.push(parse_reference_type(tokens, src)?);
         } else {
@@ -154,7 +191,7 @@ mod tests {
     #[test]
     fn test_parse_type() {
         let src = "boolean";
-        let node = parse_type(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_type(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         match node.children[0].node_type {
             NodeType::NTerm(NTermType::BasicType) => (),
@@ -162,7 +199,7 @@ mod tests {
         }
 
         let src = "SomeReferenceType";
-        let node = parse_type(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_type(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         match node.children[0].node_type {
             NodeType::NTerm(NTermType::ReferenceType) => (),
@@ -173,7 +210,7 @@ mod tests {
     #[test]
     fn test_parse_type_argument() {
         let src = "? extends T<Bar>.Foo";
-        let node = parse_type_argument(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_type_argument(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
         assert_eq!(node.children[0].val(src), Some("?"));
         assert_eq!(node.children[1].val(src), Some("extends"));
@@ -183,7 +220,7 @@ mod tests {
         }
 
         let src = "T<Foo>.Bar";
-        let node = parse_type_argument(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_type_argument(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         match node.children[0].node_type {
             NodeType::NTerm(NTermType::ReferenceType) => (),
@@ -194,7 +231,7 @@ mod tests {
     #[test]
     fn test_parse_reference_type() {
         let src = "T<Foo>.Bar";
-        let node = parse_reference_type(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_reference_type(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
         assert_eq!(node.children[0].val(src).unwrap(), "T");
         match node.children[1].node_type {
@@ -207,37 +244,80 @@ mod tests {
     #[test]
     fn test_parse_type_arguments() {
         let src = "<T<Foo>.Bar, N<MyVar>, ? extends X>";
-        let node = parse_type_arguments(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_type_arguments(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 5);
 
         let src = "<>";
-        let node = parse_type_arguments_or_diamond(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_type_arguments_or_diamond(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 2);
     }
 
     #[test]
     fn test_parse_type_list() {
         let src = "U<Foo>, V<Bar>, MyClass";
-        let node = parse_type_list(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_type_list(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
     }
 
     #[test]
     fn test_parse_non_wildcard_type_arguments() {
         let src = "<U<Foo>, V<Bar>, MyClass>";
-        let node = parse_non_wildcard_type_arguments(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_non_wildcard_type_arguments(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
 
         let src = "<>";
-        let node = parse_non_wildcard_type_arguments_or_diamond(&mut lex(src, "").unwrap().iter(),
+        let node = parse_non_wildcard_type_arguments_or_diamond(&mut TokenIter::new(&lex(src, "").unwrap()),
                                                                src).unwrap();
         assert_eq!(node.children.len(), 2);
     }
 
+    #[test]
+    fn test_parse_nested_type_arguments() {
+        let src = "Map<String, List<Integer>>";
+        let node = parse_type(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
+        let type_args = &node.children[0].children[1];
+        assert_eq!(type_args.children.len(), 4);
+        assert_eq!(type_args.children[3].val(src), Some(">"));
+        let inner = &type_args.children[2].children[0].children[1];
+        assert_eq!(inner.children[2].val(src), Some(">"));
+
+        // Every '>' closes a level, including those in '>>>' and '>>='
+        let src = "<Bar<Baz<Q>>>";
+        let tokens = lex(src, "").unwrap();
+        let mut iter = TokenIter::new(&tokens);
+        assert!(parse_type_arguments(&mut iter, src).is_ok());
+        assert!(iter.next().is_none());
+
+        let src = "<List<T>>= x";
+        let tokens = lex(src, "").unwrap();
+        let mut iter = TokenIter::new(&tokens);
+        let node = parse_type_arguments(&mut iter, src).unwrap();
+        assert_eq!(node.span().map(|(start, end)| &src[start.0..end.0]), Some("<List<T>>"));
+        assert_eq!(iter.next().map(|t| t.val(src)), Some("="));
+
+        // Too many '>'s
+        let src = "<T>> x";
+        let tokens = lex(src, "").unwrap();
+        let mut iter = TokenIter::new(&tokens);
+        assert!(parse_type_arguments(&mut iter, src).is_ok());
+        assert_eq!(iter.next().map(|t| t.val(src)), Some(">"));
+    }
+
+    #[test]
+    fn test_parse_type_parameters() {
+        let src = "<K extends Comparable<K>, V extends Foo & Bar<List<V>>>";
+        let tokens = lex(src, "").unwrap();
+        let mut iter = TokenIter::new(&tokens);
+        let node = parse_type_parameters(&mut iter, src).unwrap();
+        assert_eq!(node.children.len(), 4);
+        assert_eq!(node.children[2].children.len(), 3);
+        assert!(iter.next().is_none());
+    }
+
     #[test]
     fn test_parse_bound() {
         let src = "SomeClass1 & SomeClass2 & SomeClass3";
-        let node = parse_bound(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_bound(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
     }
 }
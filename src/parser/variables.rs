@@ -5,7 +5,7 @@ use lexer::TokenType;
 
 pub fn parse_variable_modifier(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let child = match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "final" => term(*tokens.next().unwrap()),
+        Some(tok) if tok.kind == TokenKind::Final => term(tokens.next().unwrap()),
         _ => parse_annotation(tokens, src)?,
     };
     Ok(nterm(NTermType::VariableModifier, vec![child]))
@@ -13,31 +13,31 @@ pub fn parse_variable_modifier(tokens: &mut TokenIter, src: &str) -> ParseRes {
 
 #[allow(dead_code)]
 pub fn parse_array_initializer(tokens: &mut TokenIter, src: &str) -> ParseRes {
-    let mut children = vec![assert_term(tokens, src, "{")?];
+    let mut children = vec![assert_term(tokens, src, TokenKind::LBrace)?];
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "}" => {
-            children.push(term(*tokens.next().unwrap()));
+        Some(tok) if tok.kind == TokenKind::RBrace => {
+            children.push(term(tokens.next().unwrap()));
             return Ok(nterm(NTermType::ArrayInitializer, children))
         }
         _ => children.push(parse_variable_initializer(tokens, src)?),
     }
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
+        if tok.kind == TokenKind::Comma {
             tokens.next(); // Skip ','
             match tokens.clone().next() {
-                Some(tok) if tok.val(src) == "}" => break,
+                Some(tok) if tok.kind == TokenKind::RBrace => break,
                 _ => children.push(parse_variable_initializer(tokens, src)?),
             }
         } else { break; }
     }
-    children.push(assert_term(tokens, src, "}")?);
+    children.push(assert_term(tokens, src, TokenKind::RBrace)?);
     Ok(nterm(NTermType::ArrayInitializer, children))
 }
 
 #[allow(dead_code)]
 pub fn parse_variable_initializer(tokens: &mut TokenIter, src: &str) -> ParseRes {
     Ok(nterm(NTermType::VariableInitializer, match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "{" => vec![parse_array_initializer(tokens, src)?],
+        Some(tok) if tok.kind == TokenKind::LBrace => vec![parse_array_initializer(tokens, src)?],
         _ => vec![parse_expression(tokens, src)?],
     }))
 }
@@ -46,14 +46,14 @@ pub fn parse_variable_initializer(tokens: &mut TokenIter, src: &str) -> ParseRes
 pub fn parse_variable_declarator_rest(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = Vec::new();
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "[" {
-            children.push(term(*tokens.next().unwrap()));
-            children.push(assert_term(tokens, src, "]")?);
+        if tok.kind == TokenKind::LBracket {
+            children.push(term(tokens.next().unwrap()));
+            children.push(assert_term(tokens, src, TokenKind::RBracket)?);
         } else { break }
     }
     match tokens.clone().next() {
-        Some(tok) if tok.val(src) == "=" => {
-            children.push(term(*tokens.next().unwrap()));
+        Some(tok) if tok.kind == TokenKind::Eq => {
+            children.push(term(tokens.next().unwrap()));
             children.push(parse_variable_initializer(tokens, src)?);
         }
         _ => ()
@@ -72,7 +72,7 @@ pub fn parse_variable_declarator(tokens: &mut TokenIter, src: &str) -> ParseRes
 pub fn parse_variable_declarators(tokens: &mut TokenIter, src: &str) -> ParseRes {
     let mut children = vec![parse_variable_declarator(tokens, src)?];
     while let Some(tok) = tokens.clone().next() {
-        if tok.val(src) == "," {
+        if tok.kind == TokenKind::Comma {
             tokens.next().unwrap(); // Skip ','
             children.push(parse_variable_declarator(tokens, src)?);
         } else { break; }
@@ -89,7 +89,7 @@ mod tests {
     #[test]
     fn test_parse_variable_modifier() {
         let src = "@MyAnnotation";
-        let node = parse_variable_modifier(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_variable_modifier(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         match node.children[0].node_type {
             NodeType::NTerm(NTermType::Annotation) => (),
@@ -97,7 +97,7 @@ mod tests {
         }
 
         let src = "final";
-        let node = parse_variable_modifier(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_variable_modifier(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 1);
         assert_eq!(node.children[0].val(src), Some("final"));
     }
@@ -105,7 +105,7 @@ mod tests {
     #[test]
     fn test_parse_variable_declarators() {
         let src = "foo = \"hello\", bar = 3, baz = {1, 2, 3}";
-        let node = parse_variable_declarators(&mut lex(src, "").unwrap().iter(), src).unwrap();
+        let node = parse_variable_declarators(&mut TokenIter::new(&lex(src, "").unwrap()), src).unwrap();
         assert_eq!(node.children.len(), 3);
     }
 }